@@ -0,0 +1,173 @@
+use proc_macro2::TokenStream;
+use syn;
+
+use Error;
+
+/// Options controlling the layout of formatted code.
+///
+/// ```
+/// # extern crate minifmt;
+/// # #[macro_use] extern crate quote;
+/// # fn main() {
+/// let out = minifmt::Config::new()
+///     .indent_width(2)
+///     .max_width(80)
+///     .fmt(quote!(fn foo() { bar() }))
+///     .unwrap();
+///
+/// assert_eq!(out, "fn foo() {\n  bar()\n}\n");
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub(crate) indent_width: usize,
+    pub(crate) hard_tabs: bool,
+    pub(crate) newline_style: NewlineStyle,
+    pub(crate) max_width: usize,
+    pub(crate) trailing_comma: TrailingComma,
+    pub(crate) blank_lines_between_items: usize,
+    pub(crate) blank_lines_between_impl_items: usize,
+    pub(crate) blank_lines_after_uses: usize,
+    pub(crate) compact_empty_bodies: bool,
+}
+
+/// The line ending written after each line
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum NewlineStyle {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// `\r\n` on Windows, `\n` everywhere else
+    Native,
+}
+
+/// When the last element of a comma-separated list is followed by a comma
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TrailingComma {
+    /// Always, even when the list is on one line
+    Always,
+    /// Never, even when the list is laid out one element per line
+    Never,
+    /// Only when the list is laid out one element per line
+    Vertical,
+}
+
+/// Number of spaces per indentation level
+const DEFAULT_INDENT: usize = 4;
+
+/// Groups are broken when they would grow past this column
+const MAX_WIDTH: usize = 100;
+
+impl Config {
+    /// Returns the default configuration, which is what `minifmt::fmt` uses
+    pub fn new() -> Config {
+        Config {
+            indent_width: DEFAULT_INDENT,
+            hard_tabs: false,
+            newline_style: NewlineStyle::Lf,
+            max_width: MAX_WIDTH,
+            trailing_comma: TrailingComma::Vertical,
+            blank_lines_between_items: 1,
+            blank_lines_between_impl_items: 1,
+            blank_lines_after_uses: 1,
+            compact_empty_bodies: true,
+        }
+    }
+
+    /// Number of columns per indentation level. Defaults to 4.
+    pub fn indent_width(&mut self, width: usize) -> &mut Config {
+        self.indent_width = width;
+        self
+    }
+
+    /// Indent with one tab per level instead of spaces. A tab still counts
+    /// as `indent_width` columns towards `max_width`. Defaults to `false`.
+    pub fn hard_tabs(&mut self, hard_tabs: bool) -> &mut Config {
+        self.hard_tabs = hard_tabs;
+        self
+    }
+
+    /// Defaults to `NewlineStyle::Lf`. Newlines inside of string literals are
+    /// left alone.
+    pub fn newline_style(&mut self, style: NewlineStyle) -> &mut Config {
+        self.newline_style = style;
+        self
+    }
+
+    /// Lines are broken so they do not grow past this column where possible.
+    /// Defaults to 100.
+    pub fn max_width(&mut self, width: usize) -> &mut Config {
+        self.max_width = width;
+        self
+    }
+
+    /// Applies to struct fields, enum variants, match arms, call arguments,
+    /// generics, use groups and the other comma-separated lists. Defaults to
+    /// `TrailingComma::Vertical`.
+    pub fn trailing_comma(&mut self, trailing_comma: TrailingComma) -> &mut Config {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+
+    /// Blank lines between items in a file or module. Consecutive `use` and
+    /// `extern crate` items are kept together. Defaults to 1.
+    pub fn blank_lines_between_items(&mut self, lines: usize) -> &mut Config {
+        self.blank_lines_between_items = lines;
+        self
+    }
+
+    /// Blank lines between the items of an impl or trait. Defaults to 1.
+    pub fn blank_lines_between_impl_items(&mut self, lines: usize) -> &mut Config {
+        self.blank_lines_between_impl_items = lines;
+        self
+    }
+
+    /// Blank lines between a block of `use` and `extern crate` items and the
+    /// item that follows it. Defaults to 1.
+    pub fn blank_lines_after_uses(&mut self, lines: usize) -> &mut Config {
+        self.blank_lines_after_uses = lines;
+        self
+    }
+
+    /// Write empty bodies of modules, impls, traits, functions, match
+    /// expressions and the like as `{}` instead of an opening and a closing
+    /// brace on separate lines. Defaults to `true`.
+    pub fn compact_empty_bodies(&mut self, compact: bool) -> &mut Config {
+        self.compact_empty_bodies = compact;
+        self
+    }
+
+    /// Format a `TokenStream`
+    pub fn fmt(&self, tts: TokenStream) -> Result<String, Error> {
+        let file: syn::File = syn::parse2(tts)?;
+        Ok(self.fmt_file(&file))
+    }
+
+    /// Format an already parsed `syn::File`
+    pub fn fmt_file(&self, file: &syn::File) -> String {
+        ::format(self, file)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::new()
+    }
+}
+
+impl NewlineStyle {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match *self {
+            NewlineStyle::Lf => "\n",
+            NewlineStyle::Crlf => "\r\n",
+            NewlineStyle::Native => {
+                if cfg!(windows) {
+                    "\r\n"
+                } else {
+                    "\n"
+                }
+            }
+        }
+    }
+}
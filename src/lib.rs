@@ -1,62 +1,97 @@
 #![allow(unused_variables)]
+// Newlines written to `FormatFile` are hard breaks for the printer rather
+// than line endings, so `write!(v, ",\n")` reads better than `writeln!`.
+#![allow(clippy::write_with_newline)]
 
 extern crate proc_macro2;
+extern crate quote;
 extern crate syn;
 
+mod config;
 mod error;
 mod node;
+mod pp;
+mod prec;
 mod punct;
+mod tokens;
 
+pub use config::{Config, NewlineStyle, TrailingComma};
 pub use error::Error;
 use node::Node;
+use pp::{Breaks, Printer};
+use prec::Precedence;
 use punct::{Space, Punctuation};
 use punct::Space::*;
 
 use proc_macro2::TokenStream;
+use quote::ToTokens;
 use syn::punctuated::Punctuated;
 use syn::visit::Visit;
 
 use std::fmt::{self, Write};
+use std::mem;
 
-/// Format a `TokenStream`
+/// Format a `TokenStream` with the default `Config`
 pub fn fmt(tts: TokenStream) -> Result<String, Error> {
-    let file: syn::File = syn::parse2(tts)?;
+    Config::new().fmt(tts)
+}
 
-    let mut visitor = FormatFile::new();
-    visitor.visit_file(&file);
+/// Format an already parsed `syn::File` with the default `Config`
+///
+/// Unlike a `TokenStream`, a file parsed from source may start with a
+/// shebang line, which is written out as is.
+pub fn fmt_file(file: &syn::File) -> String {
+    Config::new().fmt_file(file)
+}
 
-    // Ensure there is a trailing newline character
-    if !visitor.out.is_empty() {
-        let idx = visitor.out.len() - 1;
+fn format(config: &Config, file: &syn::File) -> String {
+    let mut visitor = FormatFile::new(config);
+    visitor.visit_file(file);
 
-        if &visitor.out[idx..] != "\n" {
-            visitor.out.push_str("\n");
-        }
+    let mut out = visitor.pp.print(config);
+
+    // Ensure there is a trailing newline character
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push_str(config.newline_style.as_str());
     }
 
-    Ok(visitor.out)
+    out
 }
 
 struct FormatFile {
-    /// Formatted code is appended to this buffer.
-    out: String,
+    /// Formatted code is written to this printer, which lays it out once the
+    /// whole file has been visited.
+    pp: Printer,
 
-    /// Indentation level
-    indent: usize,
+    config: Config,
 
     /// Set to true when visiting a statement item
     is_stmt_item: bool,
+
+    /// Set to true when visiting an expression in statement position. The
+    /// expression's attributes then go on their own lines.
+    is_stmt_expr: bool,
+
+    /// Set to true when visiting a branch of an `if` / `else` chain. The
+    /// next block is then kept open even if it is empty, so that all of the
+    /// chain's branches line up.
+    is_open_block: bool,
 }
 
-/// Number of spaces per indentation level
-const DEFAULT_INDENT: usize = 4;
+/// Struct literals with a longer body are written one field per line
+const STRUCT_LIT_WIDTH: usize = 18;
+
+/// Arrays of literals no wider than this are packed into columns
+const SHORT_LITERAL_WIDTH: usize = 10;
 
 impl FormatFile {
-    fn new() -> FormatFile {
+    fn new(config: &Config) -> FormatFile {
         FormatFile {
-            out: "".to_string(),
-            indent: 0,
+            pp: Printer::new(),
+            config: config.clone(),
             is_stmt_item: false,
+            is_stmt_expr: false,
+            is_open_block: false,
         }
     }
 
@@ -82,59 +117,232 @@ impl FormatFile {
         }
     }
 
-    fn visit_doc_attribute(&mut self, i: &syn::Attribute) {
-        use syn::Meta::*;
-        use syn::Lit::*;
+    /// Writes each outer attribute followed by a space, for attributes that
+    /// are in the middle of a line.
+    fn visit_inline_attributes(&mut self, i: &[syn::Attribute]) {
+        for attr in i {
+            if !is_inner_attr(attr) {
+                write!(self, "#[");
+                self.visit_attribute_body(attr);
+                write!(self, "] ");
+            }
+        }
+    }
 
-        let meta = i.parse_meta().unwrap();
+    /// Writes an expression's outer attributes, on their own lines if the
+    /// expression is a statement and inline otherwise.
+    fn visit_expr_attributes(&mut self, i: &[syn::Attribute]) {
+        if self.is_stmt_expr {
+            self.is_stmt_expr = false;
+            self.visit_outer_attributes(i);
+        } else {
+            self.visit_inline_attributes(i);
+        }
+    }
 
-        match meta {
-            NameValue(name_value) => {
-                match name_value.lit {
-                    Str(s) => {
-                        self.visit_doc_comment(is_inner_attr(i), &s.value());
-                    }
-                    _ => panic!(),
-                }
-            }
-            _ => panic!(),
+    /// Writes the attributes of an expression whose left operand comes first,
+    /// such as an assignment. syn puts the attributes of a statement on its
+    /// leftmost operand, so a statement's attributes are left for it when the
+    /// expression has none of its own.
+    fn visit_spine_attributes(&mut self, i: &[syn::Attribute]) {
+        if !(self.is_stmt_expr && i.is_empty()) {
+            self.visit_expr_attributes(i);
         }
     }
 
     fn visit_doc_comment(&mut self, is_inner: bool, value: &str) {
-        if is_inner {
-            write!(self, "//!{}\n", value);
-        } else {
-            write!(self, "///{}\n", value);
+        let prefix = if is_inner { "//!" } else { "///" };
+
+        if value.is_empty() {
+            write!(self, "{}\n", prefix);
+            return;
+        }
+
+        // Each line of the doc string gets its own comment line. Trailing
+        // whitespace is kept, as it is a hard line break in Markdown.
+        for line in value.lines() {
+            write!(self, "{}{}\n", prefix, line);
         }
     }
 
     fn visit_attribute_body(&mut self, i: &syn::Attribute) {
-        let meta = i.parse_meta().unwrap();
-        self.visit_meta(&meta);
+        self.visit_meta(&i.meta);
+    }
+
+    /// Visits a block, writing `attrs` inside of the braces if they are inner
+    /// attributes.
+    fn visit_block_with_inner_attributes(&mut self, attrs: &[syn::Attribute], i: &syn::Block) {
+        self.block_no_nl(|v| {
+            v.visit_inner_attributes(attrs);
+
+            v.is_stmt_item = false;
+
+            for stmt in &i.stmts {
+                v.visit_stmt(stmt);
+            }
+        });
     }
 
     fn visit_items(&mut self, i: &[syn::Item]) {
-        for item in i {
+        for (pos, item) in i.iter().enumerate() {
+            if pos > 0 {
+                let lines = match (is_import(&i[pos - 1]), is_import(item)) {
+                    (true, true) => 0,
+                    (true, false) => self.config.blank_lines_after_uses,
+                    _ => self.config.blank_lines_between_items,
+                };
+
+                self.visit_blank_lines(lines);
+            }
+
             self.visit_item(item);
         }
     }
 
     fn visit_impl_items(&mut self, i: &[syn::ImplItem]) {
         for (pos, item) in i.iter().enumerate() {
+            if pos > 0 {
+                self.visit_blank_lines(self.config.blank_lines_between_impl_items);
+            }
+
             self.visit_impl_item(item);
+            write!(self, "\n");
+        }
+    }
 
-            if pos + 1 < i.len() {
-                write!(self, "\n");
+    fn visit_trait_items(&mut self, i: &[syn::TraitItem]) {
+        for (pos, item) in i.iter().enumerate() {
+            if pos > 0 {
+                self.visit_blank_lines(self.config.blank_lines_between_impl_items);
             }
+
+            self.visit_trait_item(item);
+            write!(self, "\n");
+        }
+    }
+
+    fn visit_blank_lines(&mut self, lines: usize) {
+        for _ in 0..lines {
             write!(self, "\n");
         }
     }
 
+    fn visit_foreign_items(&mut self, i: &[syn::ForeignItem]) {
+        for item in i {
+            self.visit_foreign_item(item);
+            write!(self, "\n");
+        }
+    }
+
+    /// Writes a macro invocation, with the name given to item macros such as
+    /// `macro_rules! foo { ... }`.
+    fn visit_macro_with_ident(&mut self, i: &syn::Macro, ident: Option<&syn::Ident>) {
+        use syn::MacroDelimiter::*;
+
+        self.visit_path(&i.path);
+        write!(self, "!");
+
+        if let Some(ident) = ident {
+            write!(self, " {} ", ident);
+        }
+
+        match i.delimiter {
+            Paren(_) => {
+                write!(self, "(");
+                self.visit_macro_body(&i.tokens);
+                write!(self, ")");
+            }
+            Bracket(_) => {
+                write!(self, "[");
+                self.visit_macro_body(&i.tokens);
+                write!(self, "]");
+            }
+            Brace(_) => {
+                if ident.is_none() {
+                    write!(self, " ");
+                }
+
+                if i.tokens.is_empty() {
+                    write!(self, "{{}}");
+                    return;
+                }
+
+                self.block_no_nl(|v| {
+                    for line in tokens::normalize_lines(i.tokens.clone()) {
+                        v.pp.text(&line);
+                        write!(v, "\n");
+                    }
+                });
+            }
+        }
+    }
+
     fn visit_macro_body(&mut self, tts: &TokenStream) {
-        // TODO: How to do this?
-        // Should it be parsed as `syn::Stmt`?
-        write!(self, "{}", tts.to_string());
+        // TODO: Should it be parsed as `syn::Stmt`?
+        //
+        // Written as a single piece of text, so that lines inside of a
+        // multi-line literal are not indented
+        self.pp.text(&tokens::normalize(tts.clone()));
+    }
+
+    /// Writes syntax that syn keeps as raw tokens, such as `dyn* Trait`.
+    fn visit_verbatim(&mut self, tts: &TokenStream) {
+        self.pp.text(&tokens::normalize(tts.clone()));
+    }
+
+    /// Writes a path, including the qualified self type if there is one.
+    fn visit_qualified_path(&mut self, qself: &Option<syn::QSelf>, path: &syn::Path) {
+        let qself = match *qself {
+            Some(ref qself) => qself,
+            None => {
+                self.visit_path(path);
+                return;
+            }
+        };
+
+        write!(self, "<");
+        self.visit_type(&qself.ty);
+
+        let pos = if qself.position > 0 && qself.position >= path.segments.len() {
+            path.segments.len() - 1
+        } else {
+            qself.position
+        };
+
+        if pos > 0 {
+            write!(self, " as ");
+        } else {
+            write!(self, ">");
+        }
+
+        if path.leading_colon.is_some() {
+            write!(self, "::");
+        }
+
+        for (idx, segment) in path.segments.iter().enumerate() {
+            if idx > 0 {
+                if idx == pos {
+                    write!(self, ">::");
+                } else {
+                    write!(self, "::");
+                }
+            }
+
+            self.visit_path_segment(segment);
+        }
+    }
+
+    /// Writes a literal exactly as its token was spelled
+    fn visit_literal_token<T: ToTokens>(&mut self, lit: &T) {
+        // A negative number is a `-` token followed by the literal
+        let lit: String = lit.into_token_stream()
+            .into_iter()
+            .map(|tt| tt.to_string())
+            .collect();
+
+        // Written as a single piece of text, so that lines inside of a
+        // multi-line literal are not indented
+        self.pp.text(&lit);
     }
 
     /// Returns `true` if where clause was present
@@ -148,6 +356,167 @@ impl FormatFile {
         }
     }
 
+    /// Writes the condition of an `if` or `while`, the scrutinee of a `match`
+    /// or the iterator of a `for`, which are followed by a block
+    fn visit_head(&mut self, expr: &syn::Expr) {
+        // The `let` of `if let` adds any parentheses its expression needs
+        let parens = match *expr {
+            syn::Expr::Let(_) => false,
+            _ => has_exterior_struct(expr),
+        };
+
+        self.visit_operand(expr, parens);
+    }
+
+    /// Writes the where clause of a type alias or associated type, which goes
+    /// after the type and is closed by the `;` that follows.
+    fn visit_trailing_where_clause(&mut self, generics: &syn::Generics) {
+        if let Some(ref where_clause) = generics.where_clause {
+            write!(self, "\nwhere");
+
+            self.indent(|v| {
+                write!(v, "\n");
+
+                for (pos, predicate) in where_clause.predicates.iter().enumerate() {
+                    if pos > 0 {
+                        write!(v, ",\n");
+                    }
+
+                    v.visit_where_predicate(predicate);
+                }
+            });
+        }
+    }
+
+    /// Visits an operand, wrapping it in parentheses when `parens` is set.
+    ///
+    /// Parentheses written in the source are kept by `visit_expr_paren`,
+    /// this is only needed for operands that arrive as invisible groups.
+    fn visit_operand(&mut self, expr: &syn::Expr, parens: bool) {
+        if parens {
+            self.is_stmt_expr = false;
+            write!(self, "(");
+            self.visit_expr(expr);
+            write!(self, ")");
+        } else {
+            self.visit_expr(expr);
+        }
+    }
+
+    /// Visits the type under a `&`, a raw pointer or the `->` of a bare fn,
+    /// where a bound list that arrived in an invisible group needs
+    /// parentheses to keep its `+` from being ambiguous.
+    fn visit_type_operand(&mut self, ty: &syn::Type) {
+        if has_multiple_bounds(ty) {
+            write!(self, "(");
+            self.visit_type(ty);
+            write!(self, ")");
+        } else {
+            self.visit_type(ty);
+        }
+    }
+
+    /// Writes an `if` and the `else` branches that follow it, keeping every
+    /// empty branch open if `open` is set.
+    fn visit_if_chain(&mut self, i: &syn::ExprIf, open: bool) {
+        write!(self, "if ");
+        self.visit_head(&i.cond);
+        write!(self, " ");
+
+        self.is_open_block = open;
+        self.visit_block(&i.then_branch);
+
+        if let Some((_, ref else_branch)) = i.else_branch {
+            write!(self, " else ");
+
+            match **else_branch {
+                syn::Expr::If(ref else_if) => {
+                    self.visit_if_chain(else_if, open);
+                }
+                syn::Expr::Block(ref block) => {
+                    self.is_open_block = open;
+                    self.visit_expr_block(block);
+                }
+                ref expr => {
+                    self.visit_expr(expr);
+                }
+            }
+        }
+    }
+
+    /// Writes a chain of binary operators of the same precedence as one
+    /// group, which breaks before each operator when it does not fit.
+    ///
+    /// The group holds the left operand too, so that a chain of a lower
+    /// precedence breaks before the chains inside of it do. A chain that is
+    /// the left operand of another one is not indented any further, which
+    /// lines up the operators of both.
+    fn visit_binary_chain(&mut self, i: &syn::ExprBinary, indent: usize) {
+        let prec = Precedence::of_bin_op(&i.op);
+        let mut chain = vec![i];
+
+        while let syn::Expr::Binary(ref left) = *chain[chain.len() - 1].left {
+            let last = chain[chain.len() - 1];
+
+            if Precedence::of_bin_op(&left.op) != prec ||
+                !left.attrs.is_empty() ||
+                operand_parens(last).0
+            {
+                break;
+            }
+
+            chain.push(left);
+        }
+
+        let first = chain[chain.len() - 1];
+        let left_parens = operand_parens(first).0;
+
+        // The newlines after statement attributes would break the group, so
+        // they are written before it
+        let mut stripped = None;
+
+        if self.is_stmt_expr && !left_parens {
+            let mut left = (*first.left).clone();
+            let attrs = take_leftmost_attrs(&mut left);
+
+            self.visit_expr_attributes(&attrs);
+            stripped = Some(left);
+        }
+
+        let left = stripped.as_ref().unwrap_or(&first.left);
+        self.pp.begin(indent, Breaks::Consistent);
+
+        match *left {
+            syn::Expr::Binary(ref left) if left.attrs.is_empty() && !left_parens => {
+                self.visit_binary_chain(left, 0);
+            }
+            ref left => {
+                self.visit_operand(left, left_parens);
+            }
+        }
+
+        for bin in chain.into_iter().rev() {
+            self.pp.space();
+            self.visit_bin_op(&bin.op);
+            write!(self, " ");
+            self.visit_operand(&bin.right, operand_parens(bin).1);
+        }
+
+        self.pp.end();
+    }
+
+    /// Writes a method call without its receiver
+    fn visit_method_call_suffix(&mut self, i: &syn::ExprMethodCall) {
+        write!(self, ".");
+        self.visit_ident(&i.method);
+
+        if let Some(ref turbofish) = i.turbofish {
+            self.visit_angle_bracketed_generic_arguments(turbofish);
+        }
+
+        self.visit_list("(", ")", &i.args);
+    }
+
     // ===== Formatting helpers =====
 
     fn visit_punctuated<T, U>(&mut self, punctuated: &Punctuated<T, U>, space: Space)
@@ -155,16 +524,26 @@ impl FormatFile {
         T: Node,
         U: Punctuation,
     {
-        for pair in punctuated.pairs() {
+        let len = punctuated.len();
+
+        // Lists separated by an operator, such as bounds, break before it
+        if let SpaceBoth = space {
+            self.pp.begin(1, Breaks::Consistent);
+        }
+
+        for (pos, pair) in punctuated.pairs().enumerate() {
             pair.value().visit(self);
 
+            // Trailing punctuation is dropped
+            if pos + 1 == len {
+                continue;
+            }
+
             if let Some(punct) = pair.punct() {
                 let (l, r) = match space {
-                    NewLine => {
-                        ("", "\n")
-                    }
                     SpaceBoth => {
-                        (" ", " ")
+                        self.pp.space();
+                        ("", " ")
                     }
                     SpaceRight => {
                         ("", " ")
@@ -177,22 +556,149 @@ impl FormatFile {
                 write!(self, "{}{}{}", l, punct.as_str(), r);
             }
         }
+
+        if let SpaceBoth = space {
+            self.pp.end();
+        }
+    }
+
+    /// Writes a delimited list on a single line when it fits, otherwise one
+    /// element per line.
+    fn visit_list<T: Node>(&mut self, open: &str, close: &str, list: &Punctuated<T, syn::token::Comma>) {
+        write!(self, "{}", open);
+
+        if !list.is_empty() {
+            self.pp.begin(1, Breaks::Consistent);
+            self.pp.zerobreak();
+
+            for (pos, value) in list.iter().enumerate() {
+                if pos > 0 {
+                    write!(self, ",");
+                    self.pp.space();
+                }
+
+                value.visit(self);
+            }
+
+            self.visit_trailing_comma();
+            self.pp.zerobreak();
+            self.pp.end();
+        }
+
+        write!(self, "{}", close);
+    }
+
+    /// Writes the elements of an array like `visit_list`, except that a broken
+    /// array fills each line with as many elements as fit, right-aligned in
+    /// columns.
+    fn visit_table(&mut self, cells: &[String]) {
+        let width = cells.iter().map(|cell| cell.chars().count()).max().unwrap_or(0);
+
+        write!(self, "[");
+        self.pp.begin(1, Breaks::Consistent);
+        self.pp.zerobreak();
+        self.pp.begin(0, Breaks::Inconsistent);
+
+        for (pos, cell) in cells.iter().enumerate() {
+            if pos > 0 {
+                write!(self, ",");
+                self.pp.space();
+            }
+
+            let pad = width - cell.chars().count();
+
+            if pad > 0 {
+                self.pp.if_broken(&" ".repeat(pad));
+            }
+
+            self.pp.text(cell);
+        }
+
+        self.pp.end();
+        self.visit_trailing_comma();
+        self.pp.zerobreak();
+        self.pp.end();
+        write!(self, "]");
+    }
+
+    /// Writes the trailing comma of a list that is laid out on one line if it
+    /// fits.
+    fn visit_trailing_comma(&mut self) {
+        match self.config.trailing_comma {
+            TrailingComma::Always => {
+                write!(self, ",");
+            }
+            TrailingComma::Never => {}
+            TrailingComma::Vertical => {
+                self.pp.if_broken(",");
+            }
+        }
+    }
+
+    /// Writes the trailing comma of a list that is always laid out one
+    /// element per line.
+    fn visit_vertical_trailing_comma(&mut self) {
+        match self.config.trailing_comma {
+            TrailingComma::Always | TrailingComma::Vertical => {
+                write!(self, ",");
+            }
+            TrailingComma::Never => {}
+        }
+    }
+
+    /// Writes the inputs of a variadic function like `visit_list`, except
+    /// that nothing may follow the variadic, not even a trailing comma.
+    fn visit_variadic_list<T, F>(&mut self, list: &Punctuated<T, syn::token::Comma>, variadic: F)
+    where
+        T: Node,
+        F: FnOnce(&mut Self),
+    {
+        write!(self, "(");
+        self.pp.begin(1, Breaks::Consistent);
+        self.pp.zerobreak();
+
+        for value in list {
+            value.visit(self);
+            write!(self, ",");
+            self.pp.space();
+        }
+
+        variadic(self);
+        self.pp.zerobreak();
+        self.pp.end();
+        write!(self, ")");
     }
 
     fn block<F, R>(&mut self, f: F) -> R
     where F: FnOnce(&mut Self) -> R,
     {
-        write!(self, "{{\n");
-        let res = self.indent(f);
-        write!(self, "}}\n");
+        let res = self.block_no_nl(f);
+        write!(self, "\n");
         res
     }
 
     fn block_no_nl<F, R>(&mut self, f: F) -> R
     where F: FnOnce(&mut Self) -> R,
     {
-        write!(self, "{{\n");
-        let res = self.indent(f);
+        let compact = self.config.compact_empty_bodies && !self.is_open_block;
+        self.is_open_block = false;
+
+        write!(self, "{{");
+
+        let res = self.indent(|v| {
+            let mark = v.pp.mark();
+
+            write!(v, "\n");
+            let res = f(v);
+
+            // Nothing but the newline was written
+            if compact && v.pp.mark() == mark + 1 {
+                v.pp.truncate(mark);
+            }
+
+            res
+        });
+
         write!(self, "}}");
         res
     }
@@ -200,28 +706,22 @@ impl FormatFile {
     fn indent<F, R>(&mut self, f: F) -> R
     where F: FnOnce(&mut Self) -> R
     {
-        self.indent += 1;
+        self.pp.begin(1, Breaks::Inconsistent);
         let ret = f(self);
-        self.indent -= 1;
+        self.pp.end();
         ret
     }
-
-    fn is_start_of_line(&self) -> bool {
-        self.out.is_empty() ||
-            self.out.as_bytes().last() == Some(&b'\n')
-    }
-
-    fn push_spaces(&mut self) {
-        for _ in 0..(self.indent * DEFAULT_INDENT) {
-            self.out.push_str(" ");
-        }
-}
 }
 
 impl<'a> syn::visit::Visit<'a> for FormatFile {
 
     fn visit_abi(&mut self, i: &'a syn::Abi) {
-        unimplemented!();
+        write!(self, "extern");
+
+        if let Some(ref name) = i.name {
+            write!(self, " ");
+            self.visit_lit_str(name);
+        }
     }
 
     fn visit_angle_bracketed_generic_arguments(&mut self, i: &'a syn::AngleBracketedGenericArguments) {
@@ -229,61 +729,43 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             write!(self, "::");
         }
 
-        write!(self, "<");
-        self.visit_punctuated(&i.args, SpaceRight);
-        write!(self, ">");
+        self.visit_list("<", ">", &i.args);
     }
 
-    fn visit_arg_captured(&mut self, i: &'a syn::ArgCaptured) {
+    fn visit_arm(&mut self, i: &'a syn::Arm) {
+        self.visit_attributes(&i.attrs);
         self.visit_pat(&i.pat);
-        write!(self, ": ");
-        self.visit_type(&i.ty);
-    }
 
-    fn visit_arg_self(&mut self, i: &'a syn::ArgSelf) {
-        if i.mutability.is_some() {
-            write!(self, "mut ");
+        if let Some((_, ref guard)) = i.guard {
+            write!(self, " if ");
+            self.visit_expr(guard);
         }
 
-        write!(self, "self");
-    }
-
-    fn visit_arg_self_ref(&mut self, i: &'a syn::ArgSelfRef) {
-        write!(self, "&");
-
-        if let Some(ref lt) = i.lifetime {
-            self.visit_lifetime(lt);
-        }
-
-        if i.mutability.is_some() {
-            if i.lifetime.is_some() {
-                write!(self, " ");
-            }
-
-            write!(self, "mut ");
-        }
+        write!(self, " => ");
 
-        write!(self, "self");
+        self.visit_expr(&i.body);
     }
 
-    fn visit_arm(&mut self, i: &'a syn::Arm) {
-        self.visit_attributes(&i.attrs);
+    fn visit_assoc_const(&mut self, i: &'a syn::AssocConst) {
+        self.visit_ident(&i.ident);
 
-        if i.leading_vert.is_some() {
-            write!(self, "| ");
+        if let Some(ref generics) = i.generics {
+            self.visit_angle_bracketed_generic_arguments(generics);
         }
 
-        self.visit_punctuated(&i.pats, SpaceBoth);
-
-        write!(self, " => ");
+        write!(self, " = ");
+        self.visit_expr(&i.value);
+    }
 
-        self.visit_expr(&i.body);
+    fn visit_assoc_type(&mut self, i: &'a syn::AssocType) {
+        self.visit_ident(&i.ident);
 
-        if i.comma.is_some() {
-            write!(self, ",");
+        if let Some(ref generics) = i.generics {
+            self.visit_angle_bracketed_generic_arguments(generics);
         }
 
-        write!(self, "\n");
+        write!(self, " = ");
+        self.visit_type(&i.ty);
     }
 
     fn visit_attr_style(&mut self, i: &'a syn::AttrStyle) {
@@ -291,8 +773,8 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_attribute(&mut self, i: &'a syn::Attribute) {
-        if is_doc_attr(&i) {
-            self.visit_doc_attribute(i);
+        if let Some(doc) = doc_comment(i) {
+            self.visit_doc_comment(is_inner_attr(i), &doc);
         } else if is_inner_attr(i) {
             write!(self, "#![");
             self.visit_attribute_body(i);
@@ -305,11 +787,25 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_bare_fn_arg(&mut self, i: &'a syn::BareFnArg) {
-        unimplemented!();
+        self.visit_inline_attributes(&i.attrs);
+
+        if let Some((ref name, _)) = i.name {
+            self.visit_ident(name);
+            write!(self, ": ");
+        }
+
+        self.visit_type(&i.ty);
     }
 
-    fn visit_bare_fn_arg_name(&mut self, i: &'a syn::BareFnArgName) {
-        unimplemented!();
+    fn visit_bare_variadic(&mut self, i: &'a syn::BareVariadic) {
+        self.visit_inline_attributes(&i.attrs);
+
+        if let Some((ref name, _)) = i.name {
+            self.visit_ident(name);
+            write!(self, ": ");
+        }
+
+        write!(self, "...");
     }
 
     fn visit_bin_op(&mut self, i: &'a syn::BinOp) {
@@ -334,370 +830,684 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             Ne(_) => "!=",
             Ge(_) => ">=",
             Gt(_) => ">",
-            AddEq(_) => "+=",
-            SubEq(_) => "-=",
-            MulEq(_) => "*=",
-            DivEq(_) => "/=",
-            RemEq(_) => "%=",
-            BitXorEq(_) => "^=",
-            BitAndEq(_) => "&=",
-            BitOrEq(_) => "|=",
-            ShlEq(_) => "<<=",
-            ShrEq(_) => ">>=",
+            AddAssign(_) => "+=",
+            SubAssign(_) => "-=",
+            MulAssign(_) => "*=",
+            DivAssign(_) => "/=",
+            RemAssign(_) => "%=",
+            BitXorAssign(_) => "^=",
+            BitAndAssign(_) => "&=",
+            BitOrAssign(_) => "|=",
+            ShlAssign(_) => "<<=",
+            ShrAssign(_) => ">>=",
+            _ => unimplemented!(),
         });
     }
 
-    fn visit_binding(&mut self, i: &'a syn::Binding) {
-        unimplemented!();
-    }
-
     fn visit_block(&mut self, i: &'a syn::Block) {
-        self.block_no_nl(|v| {
-            v.is_stmt_item = false;
-
-            for stmt in &i.stmts {
-                v.visit_stmt(stmt);
-            }
-        });
+        self.visit_block_with_inner_attributes(&[], i);
     }
 
     fn visit_bound_lifetimes(&mut self, i: &'a syn::BoundLifetimes) {
-        unimplemented!();
+        write!(self, "for<");
+        self.visit_punctuated(&i.lifetimes, SpaceRight);
+        write!(self, "> ");
     }
 
     fn visit_const_param(&mut self, i: &'a syn::ConstParam) {
-        unimplemented!();
+        self.visit_inline_attributes(&i.attrs);
+        write!(self, "const ");
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+
+        if let Some(ref default) = i.default {
+            write!(self, " = ");
+            self.visit_expr(default);
+        }
     }
 
     fn visit_constraint(&mut self, i: &'a syn::Constraint) {
-        unimplemented!();
+        self.visit_ident(&i.ident);
+
+        if let Some(ref generics) = i.generics {
+            self.visit_angle_bracketed_generic_arguments(generics);
+        }
+
+        write!(self, ": ");
+        self.visit_punctuated(&i.bounds, SpaceBoth);
     }
 
-    fn visit_expr_array(&mut self, i: &'a syn::ExprArray) {
-        unimplemented!();
+    fn visit_expr(&mut self, i: &'a syn::Expr) {
+        match *i {
+            syn::Expr::Verbatim(ref tts) => {
+                self.is_stmt_expr = false;
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_expr(self, i);
+            }
+        }
     }
 
-    fn visit_expr_assign(&mut self, i: &'a syn::ExprAssign) {
-        unimplemented!();
+    fn visit_expr_array(&mut self, i: &'a syn::ExprArray) {
+        self.visit_expr_attributes(&i.attrs);
+
+        let cells: Option<Vec<String>> = if i.elems.len() > 1 {
+            i.elems.iter().map(short_literal).collect()
+        } else {
+            None
+        };
+
+        match cells {
+            Some(cells) => self.visit_table(&cells),
+            None => self.visit_list("[", "]", &i.elems),
+        }
     }
 
-    fn visit_expr_assign_op(&mut self, i: &'a syn::ExprAssignOp) {
-        unimplemented!();
+    fn visit_expr_assign(&mut self, i: &'a syn::ExprAssign) {
+        self.visit_spine_attributes(&i.attrs);
+        self.visit_operand(&i.left, Precedence::of(&i.left) <= Precedence::Assign);
+        write!(self, " = ");
+        self.visit_operand(&i.right, Precedence::of(&i.right) < Precedence::Assign);
     }
 
     fn visit_expr_async(&mut self, i: &'a syn::ExprAsync) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "async ");
+
+        if i.capture.is_some() {
+            write!(self, "move ");
+        }
+
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
     }
 
     fn visit_expr_binary(&mut self, i: &'a syn::ExprBinary) {
-        self.visit_attributes(&i.attrs);
-        self.visit_expr(&i.left);
-        write!(self, " ");
-        self.visit_bin_op(&i.op);
-        write!(self, " ");
-        self.visit_expr(&i.right);
+        let prec = Precedence::of_bin_op(&i.op);
+
+        self.visit_spine_attributes(&i.attrs);
+
+        if prec == Precedence::Assign {
+            let (left_parens, right_parens) = operand_parens(i);
+
+            self.visit_operand(&i.left, left_parens);
+            write!(self, " ");
+            self.visit_bin_op(&i.op);
+            write!(self, " ");
+            self.visit_operand(&i.right, right_parens);
+            return;
+        }
+
+        self.visit_binary_chain(i, 1);
     }
 
-    fn visit_expr_block(&mut self, i: &'a syn::ExprBlock) {
-        self.visit_attributes(&i.attrs);
-        assert!(i.label.is_none()); // unimplemented!();
-        self.visit_block(&i.block);
+    fn visit_expr_await(&mut self, i: &'a syn::ExprAwait) {
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_operand(&i.base, Precedence::of(&i.base) < Precedence::Postfix);
+        write!(self, ".await");
     }
 
-    fn visit_expr_box(&mut self, i: &'a syn::ExprBox) {
-        unimplemented!();
+    fn visit_expr_block(&mut self, i: &'a syn::ExprBlock) {
+        self.visit_expr_attributes(&i.attrs);
+
+        if let Some(ref label) = i.label {
+            self.visit_label(label);
+        }
+
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
     }
 
     fn visit_expr_break(&mut self, i: &'a syn::ExprBreak) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "break");
+
+        if let Some(ref label) = i.label {
+            write!(self, " ");
+            self.visit_lifetime(label);
+        }
+
+        if let Some(ref expr) = i.expr {
+            write!(self, " ");
+            self.visit_expr(expr);
+        }
     }
 
     fn visit_expr_call(&mut self, i: &'a syn::ExprCall) {
-        self.visit_attributes(&i.attrs);
-        self.visit_expr(&i.func);
-        write!(self, "(");
-        self.visit_punctuated(&i.args, SpaceRight);
-        write!(self, ")");
+        self.visit_expr_attributes(&i.attrs);
+        // A field callee written without parentheses would be a method call
+        let parens = Precedence::of(&i.func) < Precedence::Postfix || is_field(&i.func);
+        self.visit_operand(&i.func, parens);
+        self.visit_list("(", ")", &i.args);
     }
 
     fn visit_expr_cast(&mut self, i: &'a syn::ExprCast) {
-        unimplemented!();
+        self.visit_spine_attributes(&i.attrs);
+        self.visit_operand(&i.expr, Precedence::of(&i.expr) < Precedence::Cast);
+        write!(self, " as ");
+        self.visit_type(&i.ty);
     }
 
     fn visit_expr_closure(&mut self, i: &'a syn::ExprClosure) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+
+        if let Some(ref lifetimes) = i.lifetimes {
+            self.visit_bound_lifetimes(lifetimes);
+        }
+
+        if i.constness.is_some() {
+            write!(self, "const ");
+        }
+
+        if i.movability.is_some() {
+            write!(self, "static ");
+        }
+
+        if i.asyncness.is_some() {
+            write!(self, "async ");
+        }
+
+        if i.capture.is_some() {
+            write!(self, "move ");
+        }
+
+        write!(self, "|");
+        self.visit_punctuated(&i.inputs, SpaceRight);
+        write!(self, "| ");
+
+        if let syn::ReturnType::Type(_, ref ty) = i.output {
+            write!(self, "-> ");
+            self.visit_type(ty);
+            write!(self, " ");
+        }
+
+        self.visit_expr(&i.body);
     }
 
+    fn visit_expr_const(&mut self, i: &'a syn::ExprConst) {
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "const ");
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
+    }
 
     fn visit_expr_continue(&mut self, i: &'a syn::ExprContinue) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "continue");
+
+        if let Some(ref label) = i.label {
+            write!(self, " ");
+            self.visit_lifetime(label);
+        }
     }
 
     fn visit_expr_field(&mut self, i: &'a syn::ExprField) {
-        self.visit_attributes(&i.attrs);
-        self.visit_expr(&i.base);
+        self.visit_expr_attributes(&i.attrs);
+        // `1.0` would lex as a float literal
+        let parens = Precedence::of(&i.base) < Precedence::Postfix || is_int_lit(&i.base);
+        self.visit_operand(&i.base, parens);
         write!(self, ".");
         self.visit_member(&i.member);
     }
 
     fn visit_expr_for_loop(&mut self, i: &'a syn::ExprForLoop) {
-        self.visit_attributes(&i.attrs);
-        if let Some(ref _label) = i.label {
-            unimplemented!();
-            // TODO: Where should the new line go?
-            // self.visit_label(label);
+        self.visit_expr_attributes(&i.attrs);
+
+        if let Some(ref label) = i.label {
+            self.visit_label(label);
         }
+
         write!(self, "for ");
         self.visit_pat(&i.pat);
         write!(self, " in ");
-        self.visit_expr(&i.expr);
+        self.visit_head(&i.expr);
         write!(self, " ");
-        self.visit_block(&i.body);
+        self.visit_block_with_inner_attributes(&i.attrs, &i.body);
     }
 
     fn visit_expr_group(&mut self, i: &'a syn::ExprGroup) {
-        unimplemented!();
+        // Invisible delimiters are dropped. Any parentheses needed to keep
+        // the precedence are added by the enclosing expression.
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_expr(&i.expr);
     }
 
     fn visit_expr_if(&mut self, i: &'a syn::ExprIf) {
-        self.visit_attributes(&i.attrs);
-        write!(self, "if ");
-        self.visit_expr(&i.cond);
-        write!(self, " ");
-        self.visit_block(&i.then_branch);
+        self.visit_expr_attributes(&i.attrs);
 
-        if let Some((_, ref else_branch)) = i.else_branch {
-            write!(self, " else ");
-            self.visit_expr(else_branch);
-        }
+        // Empty branches are only compacted when there is no `else`
+        self.visit_if_chain(i, i.else_branch.is_some());
     }
 
-    fn visit_expr_in_place(&mut self, i: &'a syn::ExprInPlace) {
-        unimplemented!();
+    fn visit_expr_index(&mut self, i: &'a syn::ExprIndex) {
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_operand(&i.expr, Precedence::of(&i.expr) < Precedence::Postfix);
+        write!(self, "[");
+        self.visit_expr(&i.index);
+        write!(self, "]");
     }
 
-    fn visit_expr_index(&mut self, i: &'a syn::ExprIndex) {
-        unimplemented!();
+    fn visit_expr_infer(&mut self, i: &'a syn::ExprInfer) {
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "_");
     }
 
     fn visit_expr_let(&mut self, i: &'a syn::ExprLet) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "let ");
+        self.visit_pat(&i.pat);
+        write!(self, " = ");
+
+        let parens = Precedence::of(&i.expr) < Precedence::Let || has_exterior_struct(&i.expr);
+        self.visit_operand(&i.expr, parens);
+    }
+
+    fn visit_expr_lit(&mut self, i: &'a syn::ExprLit) {
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_lit(&i.lit);
     }
 
     fn visit_expr_loop(&mut self, i: &'a syn::ExprLoop) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+
+        if let Some(ref label) = i.label {
+            self.visit_label(label);
+        }
+
+        write!(self, "loop ");
+        self.visit_block_with_inner_attributes(&i.attrs, &i.body);
     }
 
     fn visit_expr_macro(&mut self, i: &'a syn::ExprMacro) {
-        self.visit_attributes(&i.attrs);
+        self.visit_expr_attributes(&i.attrs);
         self.visit_macro(&i.mac);
     }
 
     fn visit_expr_match(&mut self, i: &'a syn::ExprMatch) {
-        self.visit_attributes(&i.attrs);
+        self.visit_expr_attributes(&i.attrs);
         write!(self, "match ");
-        self.visit_expr(&i.expr);
+        self.visit_head(&i.expr);
         write!(self, " ");
         self.block_no_nl(|v| {
-            for arm in &i.arms {
+            v.visit_inner_attributes(&i.attrs);
+
+            for (pos, arm) in i.arms.iter().enumerate() {
                 v.visit_arm(arm);
+
+                // An arm with a block body does not need a comma
+                match *arm.body {
+                    syn::Expr::Block(ref block) if block.attrs.is_empty() => {}
+                    _ if pos + 1 == i.arms.len() => v.visit_vertical_trailing_comma(),
+                    _ => {
+                        write!(v, ",");
+                    }
+                }
+
+                write!(v, "\n");
             }
         });
     }
 
     fn visit_expr_method_call(&mut self, i: &'a syn::ExprMethodCall) {
-        self.visit_attributes(&i.attrs);
-        self.visit_expr(&i.receiver);
-        write!(self, ".");
-        self.visit_ident(&i.method);
-        assert!(i.turbofish.is_none());
-        write!(self, "(");
-        self.visit_punctuated(&i.args, SpaceRight);
-        write!(self, ")");
+        use syn::Expr::*;
+
+        self.visit_expr_attributes(&i.attrs);
+
+        // Walk down to the start of the chain, collecting its links
+        let mut links = vec![];
+        let mut root = &*i.receiver;
+
+        loop {
+            let next = match *root {
+                MethodCall(ref m) if m.attrs.is_empty() => &m.receiver,
+                Await(ref a) if a.attrs.is_empty() => &a.base,
+                Field(ref f) if f.attrs.is_empty() => &f.base,
+                Try(ref t) if t.attrs.is_empty() => &t.expr,
+                _ => break,
+            };
+
+            links.push(root);
+            root = next;
+        }
+
+        if !links.iter().any(|link| matches!(**link, MethodCall(_))) {
+            self.visit_operand(&i.receiver, Precedence::of(&i.receiver) < Precedence::Postfix);
+            self.visit_method_call_suffix(i);
+            return;
+        }
+
+        // A chain that does not fit gets one call per line. Fields directly
+        // following the start stay on its line, as in `self.items`.
+        self.visit_operand(root, Precedence::of(root) < Precedence::Postfix);
+        self.pp.begin(1, Breaks::Consistent);
+
+        let mut attached = true;
+
+        for link in links.into_iter().rev() {
+            match *link {
+                MethodCall(ref m) => {
+                    self.pp.zerobreak();
+                    attached = false;
+                    self.visit_method_call_suffix(m);
+                }
+                Await(_) => {
+                    if !attached {
+                        self.pp.zerobreak();
+                    }
+
+                    write!(self, ".await");
+                }
+                Field(ref f) => {
+                    if !attached {
+                        self.pp.zerobreak();
+                    }
+
+                    write!(self, ".");
+                    self.visit_member(&f.member);
+                }
+                Try(_) => {
+                    write!(self, "?");
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        self.pp.zerobreak();
+        self.visit_method_call_suffix(i);
+        self.pp.end();
     }
 
     fn visit_expr_paren(&mut self, i: &'a syn::ExprParen) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "(");
+        self.visit_expr(&i.expr);
+        write!(self, ")");
     }
 
     fn visit_expr_path(&mut self, i: &'a syn::ExprPath) {
-        self.visit_attributes(&i.attrs);
-        assert!(i.qself.is_none()); // unimplemented
-        self.visit_path(&i.path);
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_qualified_path(&i.qself, &i.path);
     }
 
     fn visit_expr_range(&mut self, i: &'a syn::ExprRange) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+
+        if let Some(ref start) = i.start {
+            self.visit_operand(start, Precedence::of(start) <= Precedence::Range);
+        }
+
+        self.visit_range_limits(&i.limits);
+
+        if let Some(ref end) = i.end {
+            self.visit_operand(end, Precedence::of(end) <= Precedence::Range);
+        }
+    }
+
+    fn visit_expr_raw_addr(&mut self, i: &'a syn::ExprRawAddr) {
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "&raw ");
+        self.visit_pointer_mutability(&i.mutability);
+        write!(self, " ");
+        self.visit_operand(&i.expr, prefix_operand_parens(&i.expr));
     }
 
     fn visit_expr_reference(&mut self, i: &'a syn::ExprReference) {
-        self.visit_attributes(&i.attrs);
+        self.visit_expr_attributes(&i.attrs);
         write!(self, "&");
 
         if i.mutability.is_some() {
             write!(self, "mut ");
         }
 
-        self.visit_expr(&i.expr);
+        self.visit_operand(&i.expr, prefix_operand_parens(&i.expr));
     }
 
     fn visit_expr_repeat(&mut self, i: &'a syn::ExprRepeat) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "[");
+        self.visit_expr(&i.expr);
+        write!(self, "; ");
+        self.visit_expr(&i.len);
+        write!(self, "]");
     }
 
     fn visit_expr_return(&mut self, i: &'a syn::ExprReturn) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "return");
+
+        if let Some(ref expr) = i.expr {
+            write!(self, " ");
+            self.visit_expr(expr);
+        }
     }
 
     fn visit_expr_struct(&mut self, i: &'a syn::ExprStruct) {
-        self.visit_attributes(&i.attrs);
-        self.visit_path(&i.path);
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_qualified_path(&i.qself, &i.path);
         write!(self, " ");
-        self.block_no_nl(|v| {
-            v.visit_punctuated(&i.fields, NewLine);
 
-            if let Some(_) = i.dot2_token {
-                write!(v, ".. ");
-                let rest = i.rest.as_ref().unwrap();
-                v.visit_expr(&rest);
+        if i.fields.is_empty() && i.dot2_token.is_none() {
+            write!(self, "{{}}");
+            return;
+        }
+
+        // Short struct literals stay on one line, even where a longer one
+        // would fit.
+        write!(self, "{{");
+        self.pp.begin_limited(1, Breaks::Consistent, STRUCT_LIT_WIDTH + 2);
+        self.pp.space();
+
+        for (pos, field) in i.fields.iter().enumerate() {
+            if pos > 0 {
+                write!(self, ",");
+                self.pp.space();
+            }
+
+            self.visit_field_value(field);
+        }
+
+        if i.dot2_token.is_some() {
+            if !i.fields.is_empty() {
+                write!(self, ",");
+                self.pp.space();
+            }
+
+            write!(self, "..");
+
+            if let Some(ref rest) = i.rest {
+                self.visit_expr(rest);
             }
-        })
+        } else {
+            self.visit_trailing_comma();
+        }
+
+        self.pp.space();
+        self.pp.end();
+        write!(self, "}}");
     }
 
     fn visit_expr_try(&mut self, i: &'a syn::ExprTry) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        self.visit_operand(&i.expr, Precedence::of(&i.expr) < Precedence::Postfix);
+        write!(self, "?");
     }
 
     fn visit_expr_try_block(&mut self, i: &'a syn::ExprTryBlock) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "try ");
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
     }
 
     fn visit_expr_tuple(&mut self, i: &'a syn::ExprTuple) {
-        unimplemented!();
-    }
+        self.visit_expr_attributes(&i.attrs);
 
-    fn visit_expr_type(&mut self, i: &'a syn::ExprType) {
-        unimplemented!();
+        if i.elems.len() == 1 {
+            // A single element tuple requires the trailing comma
+            write!(self, "(");
+            self.visit_expr(&i.elems[0]);
+            write!(self, ",)");
+        } else {
+            self.visit_list("(", ")", &i.elems);
+        }
     }
 
     fn visit_expr_unary(&mut self, i: &'a syn::ExprUnary) {
-        self.visit_attributes(&i.attrs);
+        self.visit_expr_attributes(&i.attrs);
         self.visit_un_op(&i.op);
-        self.visit_expr(&i.expr);
+        self.visit_operand(&i.expr, prefix_operand_parens(&i.expr));
     }
 
     fn visit_expr_unsafe(&mut self, i: &'a syn::ExprUnsafe) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "unsafe ");
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
     }
 
-    fn visit_expr_verbatim(&mut self, i: &'a syn::ExprVerbatim) {
-        unimplemented!();
-    }
+    fn visit_expr_while(&mut self, i: &'a syn::ExprWhile) {
+        self.visit_expr_attributes(&i.attrs);
 
+        if let Some(ref label) = i.label {
+            self.visit_label(label);
+        }
 
-    fn visit_expr_while(&mut self, i: &'a syn::ExprWhile) {
-        unimplemented!();
+        write!(self, "while ");
+        self.visit_head(&i.cond);
+        write!(self, " ");
+        self.visit_block_with_inner_attributes(&i.attrs, &i.body);
     }
 
-
     fn visit_expr_yield(&mut self, i: &'a syn::ExprYield) {
-        unimplemented!();
+        self.visit_expr_attributes(&i.attrs);
+        write!(self, "yield");
+
+        if let Some(ref expr) = i.expr {
+            write!(self, " ");
+            self.visit_expr(expr);
+        }
     }
 
     fn visit_field(&mut self, i: &'a syn::Field) {
         self.visit_attributes(&i.attrs);
         self.visit_visibility(&i.vis);
 
-        // TODO: Handle;
-        assert!(i.ident.is_some());
-        assert!(i.colon_token.is_some());
-
-        self.visit_ident(i.ident.as_ref().unwrap());
+        if let Some(ref ident) = i.ident {
+            self.visit_ident(ident);
+            write!(self, ": ");
+        }
 
-        write!(self, ": ");
         self.visit_type(&i.ty);
     }
 
 
     fn visit_field_pat(&mut self, i: &'a syn::FieldPat) {
-        unimplemented!();
+        self.visit_inline_attributes(&i.attrs);
+
+        // Shorthand fields only have a pattern
+        if i.colon_token.is_some() {
+            self.visit_member(&i.member);
+            write!(self, ": ");
+        }
+
+        self.visit_pat(&i.pat);
     }
 
 
     fn visit_field_value(&mut self, i: &'a syn::FieldValue) {
-        use syn::Member::*;
-
         self.visit_attributes(&i.attrs);
+        self.visit_member(&i.member);
 
-        match i.member {
-            Named(ref ident) => {
-                self.visit_ident(ident);
-                write!(self, ": ");
-                self.visit_expr(&i.expr);
-
-            }
-            Unnamed(_) => {
-                unimplemented!();
-            }
+        // Shorthand fields only have a name
+        if i.colon_token.is_some() {
+            write!(self, ": ");
+            self.visit_expr(&i.expr);
         }
     }
 
     fn visit_fields_named(&mut self, i: &'a syn::FieldsNamed) {
         self.block(|v| {
-            v.visit_punctuated(&i.named, NewLine);
+            for (pos, field) in i.named.iter().enumerate() {
+                v.visit_field(field);
+
+                if pos + 1 < i.named.len() {
+                    write!(v, ",");
+                } else {
+                    v.visit_vertical_trailing_comma();
+                }
+
+                write!(v, "\n");
+            }
         });
     }
 
     fn visit_fields_unnamed(&mut self, i: &'a syn::FieldsUnnamed) {
-        unimplemented!();
+        self.visit_list("(", ")", &i.unnamed);
     }
 
     fn visit_file(&mut self, i: &'a syn::File) {
-        assert!(i.shebang.is_none()); // unimplemented
+        if let Some(ref shebang) = i.shebang {
+            write!(self, "{}\n", shebang);
+        }
+
         self.visit_attributes(&i.attrs);
         self.visit_items(&i.items);
     }
 
-    fn visit_fn_decl(&mut self, i: &'a syn::FnDecl) {
-        self.visit_generics(&i.generics);
-
-        write!(self, "(");
-
-        self.visit_punctuated(&i.inputs, SpaceRight);
-        assert!(i.variadic.is_none()); // unimplemented
-
-        write!(self, ")");
-
-        self.visit_return_type(&i.output);
-        self.visit_where_clause_if_present(&i.generics);
-    }
-
     fn visit_foreign_item(&mut self, i: &'a syn::ForeignItem) {
-        unimplemented!();
+        match *i {
+            syn::ForeignItem::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_foreign_item(self, i);
+            }
+        }
     }
 
     fn visit_foreign_item_fn(&mut self, i: &'a syn::ForeignItemFn) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        self.visit_signature(&i.sig);
+        write!(self, ";");
     }
 
     fn visit_foreign_item_macro(&mut self, i: &'a syn::ForeignItemMacro) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_macro(&i.mac);
+
+        if i.semi_token.is_some() {
+            write!(self, ";");
+        }
     }
 
     fn visit_foreign_item_static(&mut self, i: &'a syn::ForeignItemStatic) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "static ");
+        self.visit_static_mutability(&i.mutability);
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+        write!(self, ";");
     }
 
     fn visit_foreign_item_type(&mut self, i: &'a syn::ForeignItemType) {
-        unimplemented!();
-    }
-
-    fn visit_foreign_item_verbatim(&mut self, i: &'a syn::ForeignItemVerbatim) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "type ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+        write!(self, ";");
     }
 
-    fn visit_generic_method_argument(&mut self, i: &'a syn::GenericMethodArgument) {
-        unimplemented!();
+    fn visit_generic_argument(&mut self, i: &'a syn::GenericArgument) {
+        match *i {
+            // Block arguments such as `{ N + 1 }` stay on one line
+            syn::GenericArgument::Const(syn::Expr::Block(ref block)) => {
+                self.visit_verbatim(&block.to_token_stream());
+            }
+            _ => {
+                syn::visit::visit_generic_argument(self, i);
+            }
+        }
     }
 
     fn visit_generics(&mut self, i: &'a syn::Generics) {
@@ -705,9 +1515,7 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             return;
         }
 
-        write!(self, "<");
-        self.visit_punctuated(&i.params, SpaceRight);
-        write!(self, ">");
+        self.visit_list("<", ">", &i.params);
 
         // Where clauses are not visited here
     }
@@ -716,86 +1524,178 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
         write!(self, "{}", i);
     }
 
-    /*
-     * TODO: Reorganize order?
     fn visit_impl_item(&mut self, i: &'a syn::ImplItem) {
-        unimplemented!();
+        match *i {
+            syn::ImplItem::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_impl_item(self, i);
+            }
+        }
     }
-    */
 
     fn visit_impl_item_const(&mut self, i: &'a syn::ImplItemConst) {
-        unimplemented!();
-    }
+        self.visit_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
 
-    fn visit_impl_item_existential(&mut self, i: &'a syn::ImplItemExistential) {
-        unimplemented!();
+        if i.defaultness.is_some() {
+            write!(self, "default ");
+        }
+
+        write!(self, "const ");
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+        write!(self, " = ");
+        self.visit_expr(&i.expr);
+        write!(self, ";");
     }
 
     fn visit_impl_item_macro(&mut self, i: &'a syn::ImplItemMacro) {
-        unimplemented!();
-    }
+        self.visit_attributes(&i.attrs);
+        self.visit_macro(&i.mac);
 
-    fn visit_impl_item_method(&mut self, i: &'a syn::ImplItemMethod) {
-        assert!(i.defaultness.is_none()); // unimplemented
+        if i.semi_token.is_some() {
+            write!(self, ";");
+        }
+    }
 
-        self.visit_attributes(&i.attrs);
+    fn visit_impl_item_fn(&mut self, i: &'a syn::ImplItemFn) {
+        self.visit_outer_attributes(&i.attrs);
         self.visit_visibility(&i.vis);
-        self.visit_method_sig(&i.sig);
 
-        if i.sig.decl.generics.where_clause.is_some() {
-        } else {
+        if i.defaultness.is_some() {
+            write!(self, "default ");
+        }
+
+        self.visit_signature(&i.sig);
+
+        if i.sig.generics.where_clause.is_none() {
             write!(self, " ");
         }
 
-        self.visit_block(&i.block);
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
     }
 
     fn visit_impl_item_type(&mut self, i: &'a syn::ImplItemType) {
-        unimplemented!();
-    }
+        self.visit_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
 
-    fn visit_impl_item_verbatim(&mut self, i: &'a syn::ImplItemVerbatim) {
-        unimplemented!();
+        if i.defaultness.is_some() {
+            write!(self, "default ");
+        }
+
+        write!(self, "type ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+        write!(self, " = ");
+        self.visit_type(&i.ty);
+        self.visit_trailing_where_clause(&i.generics);
+        write!(self, ";");
     }
 
     fn visit_index(&mut self, i: &'a syn::Index) {
-        unimplemented!();
+        write!(self, "{}", i.index);
     }
 
-    /*
     fn visit_item(&mut self, i: &'a syn::Item) {
-        unimplemented!();
+        match *i {
+            syn::Item::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+                write!(self, "\n");
+            }
+            _ => {
+                syn::visit::visit_item(self, i);
+            }
+        }
     }
-    */
 
     fn visit_item_const(&mut self, i: &'a syn::ItemConst) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "const ");
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+        write!(self, " = ");
+        self.visit_expr(&i.expr);
+        write!(self, ";\n");
     }
 
     fn visit_item_enum(&mut self, i: &'a syn::ItemEnum) {
-        unimplemented!();
-    }
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
 
-    fn visit_item_existential(&mut self, i: &'a syn::ItemExistential) {
-        unimplemented!();
+        write!(self, "enum {}", i.ident);
+        self.visit_generics(&i.generics);
+
+        if !self.visit_where_clause_if_present(&i.generics) {
+            write!(self, " ");
+        }
+
+        self.block(|v| {
+            for (pos, variant) in i.variants.iter().enumerate() {
+                v.visit_variant(variant);
+
+                if pos + 1 < i.variants.len() {
+                    write!(v, ",");
+                } else {
+                    v.visit_vertical_trailing_comma();
+                }
+
+                write!(v, "\n");
+            }
+        });
     }
 
     fn visit_item_extern_crate(&mut self, i: &'a syn::ItemExternCrate) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "extern crate {}", i.ident);
+
+        if let Some((_, ref rename)) = i.rename {
+            write!(self, " as {}", rename);
+        }
+
+        write!(self, ";\n");
     }
 
     fn visit_item_fn(&mut self, i: &'a syn::ItemFn) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        self.visit_signature(&i.sig);
+
+        if i.sig.generics.where_clause.is_none() {
+            write!(self, " ");
+        }
+
+        self.visit_block_with_inner_attributes(&i.attrs, &i.block);
+        write!(self, "\n");
     }
 
     fn visit_item_foreign_mod(&mut self, i: &'a syn::ItemForeignMod) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+
+        if i.unsafety.is_some() {
+            write!(self, "unsafe ");
+        }
+
+        self.visit_abi(&i.abi);
+        write!(self, " ");
+
+        self.block(|v| {
+            v.visit_inner_attributes(&i.attrs);
+            v.visit_foreign_items(&i.items);
+        });
     }
 
     fn visit_item_impl(&mut self, i: &'a syn::ItemImpl) {
         self.visit_outer_attributes(&i.attrs);
 
-        assert!(i.defaultness.is_none()); // unimplemented
+        if i.defaultness.is_some() {
+            write!(self, "default ");
+        }
 
         if i.unsafety.is_some() {
             write!(self, "unsafe ");
@@ -805,10 +1705,17 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
 
         self.visit_generics(&i.generics);
 
-        assert!(i.trait_.is_none()); // unimplemented
-
         write!(self, " ");
 
+        if let Some((ref bang, ref path, _)) = i.trait_ {
+            if bang.is_some() {
+                write!(self, "!");
+            }
+
+            self.visit_path(path);
+            write!(self, " for ");
+        }
+
         self.visit_type(&i.self_ty);
 
         if !self.visit_where_clause_if_present(&i.generics) {
@@ -822,17 +1729,32 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_item_macro(&mut self, i: &'a syn::ItemMacro) {
-        unimplemented!();
-    }
+        use syn::MacroDelimiter::*;
 
-    fn visit_item_macro2(&mut self, i: &'a syn::ItemMacro2) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_macro_with_ident(&i.mac, i.ident.as_ref());
+
+        // Only brace delimited item macros may omit the semicolon
+        let semi = match i.mac.delimiter {
+            Brace(_) => i.semi_token.is_some(),
+            _ => true,
+        };
+
+        if semi {
+            write!(self, ";");
+        }
+
+        write!(self, "\n");
     }
 
     fn visit_item_mod(&mut self, i: &'a syn::ItemMod) {
         self.visit_outer_attributes(&i.attrs);
         self.visit_visibility(&i.vis);
 
+        if i.unsafety.is_some() {
+            write!(self, "unsafe ");
+        }
+
         write!(self, "mod {}", i.ident);
 
         // TODO abstract?
@@ -840,23 +1762,33 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             Some((_, ref items)) => {
                 write!(self, " ");
                 self.block(|me| {
+                    me.visit_inner_attributes(&i.attrs);
                     me.visit_items(items);
                 });
             }
             _ => {
-                write!(self, ";");
+                write!(self, ";\n");
             }
         }
     }
 
     fn visit_item_static(&mut self, i: &'a syn::ItemStatic) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "static ");
+        self.visit_static_mutability(&i.mutability);
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+        write!(self, " = ");
+        self.visit_expr(&i.expr);
+        write!(self, ";\n");
     }
 
     fn visit_item_struct(&mut self, i: &'a syn::ItemStruct) {
         use syn::Fields::*;
 
-        self.visit_attributes(&i.attrs);
+        self.visit_outer_attributes(&i.attrs);
         self.visit_visibility(&i.vis);
 
         write!(self, "struct {}", i.ident);
@@ -879,24 +1811,77 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
         }
 
         if i.semi_token.is_some() {
-            write!(self, ";");
+            write!(self, ";\n");
         }
     }
 
     fn visit_item_trait(&mut self, i: &'a syn::ItemTrait) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+
+        if i.unsafety.is_some() {
+            write!(self, "unsafe ");
+        }
+
+        if i.auto_token.is_some() {
+            write!(self, "auto ");
+        }
+
+        write!(self, "trait ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+
+        if !i.supertraits.is_empty() {
+            write!(self, ": ");
+            self.visit_punctuated(&i.supertraits, SpaceBoth);
+        }
+
+        if !self.visit_where_clause_if_present(&i.generics) {
+            write!(self, " ");
+        }
+
+        self.block(|v| {
+            v.visit_inner_attributes(&i.attrs);
+            v.visit_trait_items(&i.items);
+        });
     }
 
     fn visit_item_trait_alias(&mut self, i: &'a syn::ItemTraitAlias) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "trait ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+        write!(self, " = ");
+        self.visit_punctuated(&i.bounds, SpaceBoth);
+        self.visit_trailing_where_clause(&i.generics);
+        write!(self, ";\n");
     }
 
     fn visit_item_type(&mut self, i: &'a syn::ItemType) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+        write!(self, "type ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+        write!(self, " = ");
+        self.visit_type(&i.ty);
+        self.visit_trailing_where_clause(&i.generics);
+        write!(self, ";\n");
     }
 
     fn visit_item_union(&mut self, i: &'a syn::ItemUnion) {
-        unimplemented!();
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_visibility(&i.vis);
+
+        write!(self, "union {}", i.ident);
+        self.visit_generics(&i.generics);
+
+        if !self.visit_where_clause_if_present(&i.generics) {
+            write!(self, " ");
+        }
+
+        self.visit_fields_named(&i.fields);
     }
 
     fn visit_item_use(&mut self, i: &'a syn::ItemUse) {
@@ -914,12 +1899,9 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
         write!(self, ";\n");
     }
 
-    fn visit_item_verbatim(&mut self, i: &'a syn::ItemVerbatim) {
-        unimplemented!();
-    }
-
     fn visit_label(&mut self, i: &'a syn::Label) {
-        unimplemented!();
+        self.visit_lifetime(&i.name);
+        write!(self, ": ");
     }
 
     fn visit_lifetime(&mut self, i: &'a syn::Lifetime) {
@@ -927,98 +1909,81 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
         self.visit_ident(&i.ident);
     }
 
-    fn visit_lifetime_def(&mut self, i: &'a syn::LifetimeDef) {
-        unimplemented!();
+    fn visit_lifetime_param(&mut self, i: &'a syn::LifetimeParam) {
+        self.visit_inline_attributes(&i.attrs);
+        self.visit_lifetime(&i.lifetime);
+
+        if !i.bounds.is_empty() {
+            write!(self, ": ");
+            self.visit_punctuated(&i.bounds, SpaceBoth);
+        }
+    }
+
+    fn visit_lit(&mut self, i: &'a syn::Lit) {
+        match *i {
+            syn::Lit::Verbatim(ref lit) => {
+                self.visit_literal_token(lit);
+            }
+            _ => {
+                syn::visit::visit_lit(self, i);
+            }
+        }
     }
 
     fn visit_lit_bool(&mut self, i: &'a syn::LitBool) {
-        unimplemented!();
+        write!(self, "{}", i.value);
     }
 
     fn visit_lit_byte(&mut self, i: &'a syn::LitByte) {
-        unimplemented!();
+        self.visit_literal_token(i);
     }
 
     fn visit_lit_byte_str(&mut self, i: &'a syn::LitByteStr) {
-        unimplemented!();
+        self.visit_literal_token(i);
+    }
+
+    fn visit_lit_cstr(&mut self, i: &'a syn::LitCStr) {
+        self.visit_literal_token(i);
     }
 
     fn visit_lit_char(&mut self, i: &'a syn::LitChar) {
-        unimplemented!();
+        self.visit_literal_token(i);
     }
 
     fn visit_lit_float(&mut self, i: &'a syn::LitFloat) {
-        unimplemented!();
+        self.visit_literal_token(i);
     }
 
     fn visit_lit_int(&mut self, i: &'a syn::LitInt) {
-        use syn::IntSuffix::*;
-
-        write!(self, "{}{}", i.value(), match i.suffix() {
-            I8 => "i8",
-            I16 => "i16",
-            I32 => "i32",
-            I64 => "i64",
-            I128 => "i128",
-            Isize => "isize",
-            U8 => "u8",
-            U16 => "u16",
-            U32 => "u32",
-            U64 => "u64",
-            U128 => "u128",
-            Usize => "usize",
-            None => "",
-        });
+        self.visit_literal_token(i);
     }
 
     fn visit_lit_str(&mut self, i: &'a syn::LitStr) {
-        write!(self, "{:?}", i.value());
-    }
-
-    fn visit_lit_verbatim(&mut self, i: &'a syn::LitVerbatim) {
-        unimplemented!();
+        self.visit_literal_token(i);
     }
 
-
     fn visit_local(&mut self, i: &'a syn::Local) {
         self.visit_attributes(&i.attrs);
         write!(self, "let ");
-        self.visit_punctuated(&i.pats, SpaceBoth);
+        self.visit_pat(&i.pat);
 
-        if let Some((_, ref ty)) = i.ty {
-            write!(self, ": ");
-            self.visit_type(ty);
+        if let Some(ref init) = i.init {
+            self.visit_local_init(init);
         }
+    }
 
-        if let Some((_, ref init)) = i.init {
-            write!(self, " = ");
-            self.visit_expr(init);
+    fn visit_local_init(&mut self, i: &'a syn::LocalInit) {
+        write!(self, " = ");
+        self.visit_expr(&i.expr);
+
+        if let Some((_, ref diverge)) = i.diverge {
+            write!(self, " else ");
+            self.visit_expr(diverge);
         }
     }
 
     fn visit_macro(&mut self, i: &'a syn::Macro) {
-        use syn::MacroDelimiter::*;
-
-        self.visit_path(&i.path);
-        write!(self, "!");
-
-        match i.delimiter {
-            Paren(_) => {
-                write!(self, "(");
-                self.visit_macro_body(&i.tts);
-                write!(self, ")");
-            }
-            Brace(_) => {
-                write!(self, "[");
-                self.visit_macro_body(&i.tts);
-                write!(self, "]");
-            }
-            Bracket(_) => {
-                self.block_no_nl(|v| {
-                    v.visit_macro_body(&i.tts);
-                });
-            }
-        }
+        self.visit_macro_with_ident(i, None);
     }
 
     fn visit_macro_delimiter(&mut self, i: &'a syn::MacroDelimiter) {
@@ -1026,49 +1991,56 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_meta_list(&mut self, i: &'a syn::MetaList) {
-        self.visit_ident(&i.ident);
-        write!(self, "(");
-        self.visit_punctuated(&i.nested, SpaceRight);
-        write!(self, ")");
-    }
+        use syn::MacroDelimiter::*;
 
-    fn visit_meta_name_value(&mut self, i: &'a syn::MetaNameValue) {
-        self.visit_ident(&i.ident);
-        write!(self, " = ");
-        self.visit_lit(&i.lit);
-    }
+        let (open, close) = match i.delimiter {
+            Paren(_) => ("(", ")"),
+            Brace(_) => (" {", "}"),
+            Bracket(_) => ("[", "]"),
+        };
 
-    fn visit_method_sig(&mut self, i: &'a syn::MethodSig) {
-        assert!(i.constness.is_none()); // unimplemented
-        assert!(i.abi.is_none()); // unimplemented
+        self.visit_path(&i.path);
 
-        if i.unsafety.is_some() {
-            write!(self, "unsafe ");
-        }
+        // Arguments that are themselves meta items can be broken over lines
+        if let Paren(_) = i.delimiter {
+            let parser = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated;
 
-        if i.asyncness.is_some() {
-            write!(self, "async ");
+            if let Ok(list) = i.parse_args_with(parser) {
+                self.visit_list("(", ")", &list);
+                return;
+            }
         }
 
-        write!(self, "fn ");
-
-        self.visit_ident(&i.ident);
-        self.visit_fn_decl(&i.decl);
+        write!(self, "{}", open);
+        self.visit_macro_body(&i.tokens);
+        write!(self, "{}", close);
     }
 
-    fn visit_method_turbofish(&mut self, i: &'a syn::MethodTurbofish) {
-        unimplemented!();
+    fn visit_meta_name_value(&mut self, i: &'a syn::MetaNameValue) {
+        self.visit_path(&i.path);
+        write!(self, " = ");
+        self.visit_expr(&i.value);
     }
 
     fn visit_parenthesized_generic_arguments(&mut self, i: &'a syn::ParenthesizedGenericArguments) {
-        unimplemented!();
+        self.visit_list("(", ")", &i.inputs);
+        self.visit_return_type(&i.output);
     }
 
-    fn visit_pat_box(&mut self, i: &'a syn::PatBox) {
-        unimplemented!();
+    fn visit_pat(&mut self, i: &'a syn::Pat) {
+        match *i {
+            syn::Pat::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_pat(self, i);
+            }
+        }
     }
 
     fn visit_pat_ident(&mut self, i: &'a syn::PatIdent) {
+        self.visit_inline_attributes(&i.attrs);
+
         if i.by_ref.is_some() {
             write!(self, "ref ");
         }
@@ -1076,58 +2048,105 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             write!(self, "mut ");
         }
         self.visit_ident(&i.ident);
-        assert!(i.subpat.is_none()); // unimplemented
-    }
 
-    fn visit_pat_lit(&mut self, i: &'a syn::PatLit) {
-        unimplemented!();
+        if let Some((_, ref subpat)) = i.subpat {
+            write!(self, " @ ");
+            self.visit_pat(subpat);
+        }
     }
 
-    fn visit_pat_macro(&mut self, i: &'a syn::PatMacro) {
-        unimplemented!();
+    fn visit_pat_or(&mut self, i: &'a syn::PatOr) {
+        self.visit_inline_attributes(&i.attrs);
+
+        if i.leading_vert.is_some() {
+            write!(self, "| ");
+        }
+
+        self.visit_punctuated(&i.cases, SpaceBoth);
     }
 
-    fn visit_pat_path(&mut self, i: &'a syn::PatPath) {
-        unimplemented!();
+    fn visit_pat_paren(&mut self, i: &'a syn::PatParen) {
+        self.visit_inline_attributes(&i.attrs);
+        write!(self, "(");
+        self.visit_pat(&i.pat);
+        write!(self, ")");
     }
 
-    fn visit_pat_range(&mut self, i: &'a syn::PatRange) {
-        unimplemented!();
+    fn visit_pat_reference(&mut self, i: &'a syn::PatReference) {
+        self.visit_inline_attributes(&i.attrs);
+        write!(self, "&");
+
+        if i.mutability.is_some() {
+            write!(self, "mut ");
+        }
+
+        self.visit_pat(&i.pat);
     }
 
-    fn visit_pat_ref(&mut self, i: &'a syn::PatRef) {
-        unimplemented!();
+    fn visit_pat_rest(&mut self, i: &'a syn::PatRest) {
+        self.visit_inline_attributes(&i.attrs);
+        write!(self, "..");
     }
 
     fn visit_pat_slice(&mut self, i: &'a syn::PatSlice) {
-        unimplemented!();
+        self.visit_inline_attributes(&i.attrs);
+        self.visit_list("[", "]", &i.elems);
     }
 
     fn visit_pat_struct(&mut self, i: &'a syn::PatStruct) {
-        unimplemented!();
+        self.visit_inline_attributes(&i.attrs);
+        self.visit_qualified_path(&i.qself, &i.path);
+
+        if i.fields.is_empty() && i.rest.is_none() {
+            write!(self, " {{}}");
+            return;
+        }
+
+        write!(self, " {{ ");
+        self.visit_punctuated(&i.fields, SpaceRight);
+
+        if let Some(ref rest) = i.rest {
+            if !i.fields.is_empty() {
+                write!(self, ", ");
+            }
+
+            self.visit_pat_rest(rest);
+        } else if self.config.trailing_comma == TrailingComma::Always {
+            // The pattern is always on one line
+            write!(self, ",");
+        }
+
+        write!(self, " }}");
     }
 
     fn visit_pat_tuple(&mut self, i: &'a syn::PatTuple) {
-        write!(self, "(");
-
-        self.visit_punctuated(&i.front, SpaceRight);
+        self.visit_inline_attributes(&i.attrs);
 
-        assert!(i.dot2_token.is_none()); // unimplemented
-        assert!(i.comma_token.is_none()); // unimplemented
-        assert!(i.back.is_empty()); // unimplemented
-        write!(self, ")");
+        if i.elems.len() == 1 && !is_pat_rest(&i.elems[0]) {
+            // A single element tuple requires the trailing comma
+            write!(self, "(");
+            self.visit_pat(&i.elems[0]);
+            write!(self, ",)");
+        } else {
+            self.visit_list("(", ")", &i.elems);
+        }
     }
 
     fn visit_pat_tuple_struct(&mut self, i: &'a syn::PatTupleStruct) {
-        self.visit_path(&i.path);
-        self.visit_pat_tuple(&i.pat);
+        self.visit_inline_attributes(&i.attrs);
+        self.visit_qualified_path(&i.qself, &i.path);
+        self.visit_list("(", ")", &i.elems);
     }
 
-    fn visit_pat_verbatim(&mut self, i: &'a syn::PatVerbatim) {
-        unimplemented!();
+    fn visit_pat_type(&mut self, i: &'a syn::PatType) {
+        self.visit_inline_attributes(&i.attrs);
+        self.visit_pat(&i.pat);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
     }
 
     fn visit_pat_wild(&mut self, i: &'a syn::PatWild) {
+        self.visit_inline_attributes(&i.attrs);
         write!(self, "_");
     }
 
@@ -1139,16 +2158,54 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
         self.visit_punctuated(&i.segments, NoSpace);
     }
 
-    fn visit_predicate_eq(&mut self, i: &'a syn::PredicateEq) {
-        unimplemented!();
+    fn visit_pointer_mutability(&mut self, i: &'a syn::PointerMutability) {
+        use syn::PointerMutability::*;
+
+        match *i {
+            Const(_) => {
+                write!(self, "const");
+            }
+            Mut(_) => {
+                write!(self, "mut");
+            }
+        }
+    }
+
+    fn visit_precise_capture(&mut self, i: &'a syn::PreciseCapture) {
+        write!(self, "use<");
+
+        for (pos, param) in i.params.iter().enumerate() {
+            if pos > 0 {
+                write!(self, ", ");
+            }
+
+            match *param {
+                syn::CapturedParam::Lifetime(ref lt) => {
+                    self.visit_lifetime(lt);
+                }
+                syn::CapturedParam::Ident(ref ident) => {
+                    self.visit_ident(ident);
+                }
+                _ => {
+                    unimplemented!();
+                }
+            }
+        }
+
+        write!(self, ">");
     }
 
     fn visit_predicate_lifetime(&mut self, i: &'a syn::PredicateLifetime) {
-        unimplemented!();
+        self.visit_lifetime(&i.lifetime);
+        write!(self, ": ");
+        self.visit_punctuated(&i.bounds, SpaceBoth);
     }
 
     fn visit_predicate_type(&mut self, i: &'a syn::PredicateType) {
-        assert!(i.lifetimes.is_none(), "{:?}", i.lifetimes); // unimplemented!();
+        if let Some(ref lifetimes) = i.lifetimes {
+            self.visit_bound_lifetimes(lifetimes);
+        }
+
         self.visit_type(&i.bounded_ty);
         write!(self, ": ");
         self.visit_punctuated(&i.bounds, SpaceBoth);
@@ -1159,31 +2216,99 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_range_limits(&mut self, i: &'a syn::RangeLimits) {
-        unimplemented!();
+        use syn::RangeLimits::*;
+
+        match *i {
+            HalfOpen(_) => {
+                write!(self, "..");
+            }
+            Closed(_) => {
+                write!(self, "..=");
+            }
+        }
+    }
+
+    fn visit_receiver(&mut self, i: &'a syn::Receiver) {
+        self.visit_inline_attributes(&i.attrs);
+
+        if let Some((_, ref lifetime)) = i.reference {
+            write!(self, "&");
+
+            if let Some(ref lt) = *lifetime {
+                self.visit_lifetime(lt);
+                write!(self, " ");
+            }
+        }
+
+        if i.mutability.is_some() {
+            write!(self, "mut ");
+        }
+
+        write!(self, "self");
+
+        // `&self` is given an implicit type, only write one that was spelled out
+        if i.colon_token.is_some() {
+            write!(self, ": ");
+            self.visit_type(&i.ty);
+        }
     }
 
     fn visit_return_type(&mut self, i: &'a syn::ReturnType) {
         use syn::ReturnType::*;
 
-        match *i {
-            Type(_, ref ty) => {
-                write!(self, " -> ");
-                self.visit_type(ty);
+        if let Type(_, ref ty) = *i {
+            write!(self, " -> ");
+            self.visit_type(ty);
+        }
+    }
+
+    fn visit_signature(&mut self, i: &'a syn::Signature) {
+        if i.constness.is_some() {
+            write!(self, "const ");
+        }
+
+        if i.asyncness.is_some() {
+            write!(self, "async ");
+        }
+
+        if i.unsafety.is_some() {
+            write!(self, "unsafe ");
+        }
+
+        if let Some(ref abi) = i.abi {
+            self.visit_abi(abi);
+            write!(self, " ");
+        }
+
+        write!(self, "fn ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+
+        match i.variadic {
+            Some(ref variadic) => {
+                self.visit_variadic_list(&i.inputs, |v| v.visit_variadic(variadic));
+            }
+            None => {
+                self.visit_list("(", ")", &i.inputs);
             }
-            _ => {}
         }
+
+        self.visit_return_type(&i.output);
+        self.visit_where_clause_if_present(&i.generics);
     }
 
-    fn visit_span(&mut self, i: &'a proc_macro2::Span) {
-        unimplemented!();
+    fn visit_static_mutability(&mut self, i: &'a syn::StaticMutability) {
+        if let syn::StaticMutability::Mut(_) = *i {
+            write!(self, "mut ");
+        }
     }
 
     fn visit_stmt(&mut self, i: &'a syn::Stmt) {
         use syn::Stmt::*;
 
-        // Some custom spacing
+        // Some custom spacing. Statement macros are spaced like expressions.
         match *i {
-            Item(_) => {
+            Item(ref item) if !is_item_macro(item) => {
                 self.is_stmt_item = true;
             }
             _ => {
@@ -1203,47 +2328,140 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             Item(ref v) => {
                 self.visit_item(v);
             }
-            Expr(ref v) => {
+            Expr(ref v, ref semi) => {
+                self.is_stmt_expr = true;
                 self.visit_expr(v);
+
+                if semi.is_some() {
+                    write!(self, ";");
+                }
+
                 write!(self, "\n");
             }
-            Semi(ref v, _) => {
-                self.visit_expr(v);
-                write!(self, ";\n");
+            Macro(ref v) => {
+                self.visit_stmt_macro(v);
+                write!(self, "\n");
             }
         }
     }
 
+    fn visit_stmt_macro(&mut self, i: &'a syn::StmtMacro) {
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_macro(&i.mac);
+
+        if i.semi_token.is_some() {
+            write!(self, ";");
+        }
+    }
+
     fn visit_trait_bound(&mut self, i: &'a syn::TraitBound) {
-        assert!(i.paren_token.is_none()); // unimplemented
-        assert!(i.lifetimes.is_none());
+        if i.paren_token.is_some() {
+            write!(self, "(");
+        }
+
+        if let Some(ref lifetimes) = i.lifetimes {
+            self.visit_bound_lifetimes(lifetimes);
+        }
 
         self.visit_trait_bound_modifier(&i.modifier);
         self.visit_path(&i.path);
+
+        if i.paren_token.is_some() {
+            write!(self, ")");
+        }
+    }
+
+    fn visit_trait_bound_modifier(&mut self, i: &'a syn::TraitBoundModifier) {
+        match *i {
+            syn::TraitBoundModifier::Maybe(_) => {
+                write!(self, "?");
+            }
+            syn::TraitBoundModifier::None => {}
+        }
     }
 
     fn visit_trait_item(&mut self, i: &'a syn::TraitItem) {
-        unimplemented!();
+        match *i {
+            syn::TraitItem::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_trait_item(self, i);
+            }
+        }
     }
 
     fn visit_trait_item_const(&mut self, i: &'a syn::TraitItemConst) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        write!(self, "const ");
+        self.visit_ident(&i.ident);
+        write!(self, ": ");
+        self.visit_type(&i.ty);
+
+        if let Some((_, ref default)) = i.default {
+            write!(self, " = ");
+            self.visit_expr(default);
+        }
+
+        write!(self, ";");
     }
 
     fn visit_trait_item_macro(&mut self, i: &'a syn::TraitItemMacro) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_macro(&i.mac);
+
+        if i.semi_token.is_some() {
+            write!(self, ";");
+        }
     }
 
-    fn visit_trait_item_method(&mut self, i: &'a syn::TraitItemMethod) {
-        unimplemented!();
+    fn visit_trait_item_fn(&mut self, i: &'a syn::TraitItemFn) {
+        self.visit_outer_attributes(&i.attrs);
+        self.visit_signature(&i.sig);
+
+        match i.default {
+            Some(ref block) => {
+                if i.sig.generics.where_clause.is_none() {
+                    write!(self, " ");
+                }
+
+                self.visit_block_with_inner_attributes(&i.attrs, block);
+            }
+            None => {
+                write!(self, ";");
+            }
+        }
     }
 
     fn visit_trait_item_type(&mut self, i: &'a syn::TraitItemType) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        write!(self, "type ");
+        self.visit_ident(&i.ident);
+        self.visit_generics(&i.generics);
+
+        if !i.bounds.is_empty() {
+            write!(self, ": ");
+            self.visit_punctuated(&i.bounds, SpaceBoth);
+        }
+
+        if let Some((_, ref default)) = i.default {
+            write!(self, " = ");
+            self.visit_type(default);
+        }
+
+        self.visit_trailing_where_clause(&i.generics);
+        write!(self, ";");
     }
 
-    fn visit_trait_item_verbatim(&mut self, i: &'a syn::TraitItemVerbatim) {
-        unimplemented!();
+    fn visit_type(&mut self, i: &'a syn::Type) {
+        match *i {
+            syn::Type::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_type(self, i);
+            }
+        }
     }
 
     fn visit_type_array(&mut self, i: &'a syn::TypeArray) {
@@ -1255,60 +2473,101 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_type_bare_fn(&mut self, i: &'a syn::TypeBareFn) {
-        unimplemented!();
+        if let Some(ref lifetimes) = i.lifetimes {
+            self.visit_bound_lifetimes(lifetimes);
+        }
+
+        if i.unsafety.is_some() {
+            write!(self, "unsafe ");
+        }
+
+        if let Some(ref abi) = i.abi {
+            self.visit_abi(abi);
+            write!(self, " ");
+        }
+
+        write!(self, "fn");
+
+        match i.variadic {
+            Some(ref variadic) => {
+                self.visit_variadic_list(&i.inputs, |v| v.visit_bare_variadic(variadic));
+            }
+            None => {
+                self.visit_list("(", ")", &i.inputs);
+            }
+        }
+
+        if let syn::ReturnType::Type(_, ref ty) = i.output {
+            write!(self, " -> ");
+            self.visit_type_operand(ty);
+        }
     }
 
     fn visit_type_group(&mut self, i: &'a syn::TypeGroup) {
-        unimplemented!();
+        self.visit_type(&i.elem);
     }
 
     fn visit_type_impl_trait(&mut self, i: &'a syn::TypeImplTrait) {
-        unimplemented!();
+        write!(self, "impl ");
+        self.visit_punctuated(&i.bounds, SpaceBoth);
     }
 
     fn visit_type_infer(&mut self, i: &'a syn::TypeInfer) {
-        unimplemented!();
+        write!(self, "_");
     }
 
     fn visit_type_macro(&mut self, i: &'a syn::TypeMacro) {
-        unimplemented!();
+        self.visit_macro(&i.mac);
     }
 
     fn visit_type_never(&mut self, i: &'a syn::TypeNever) {
-        unimplemented!();
+        write!(self, "!");
     }
 
     fn visit_type_param(&mut self, i: &'a syn::TypeParam) {
-        // This will need special handling
-        assert!(i.attrs.is_empty());
-
+        self.visit_inline_attributes(&i.attrs);
         self.visit_ident(&i.ident);
 
-        if i.colon_token.is_none() {
-            assert!(i.bounds.is_empty());
-        } else {
+        if !i.bounds.is_empty() {
             write!(self, ": ");
             self.visit_punctuated(&i.bounds, SpaceBoth);
         }
 
         if let Some(ref ty) = i.default {
-            assert!(i.eq_token.is_some());
             write!(self, " = ");
             self.visit_type(ty);
         }
     }
 
+    fn visit_type_param_bound(&mut self, i: &'a syn::TypeParamBound) {
+        match *i {
+            syn::TypeParamBound::Verbatim(ref tts) => {
+                self.visit_verbatim(tts);
+            }
+            _ => {
+                syn::visit::visit_type_param_bound(self, i);
+            }
+        }
+    }
+
     fn visit_type_paren(&mut self, i: &'a syn::TypeParen) {
-        unimplemented!();
+        write!(self, "(");
+        self.visit_type(&i.elem);
+        write!(self, ")");
     }
 
     fn visit_type_path(&mut self, i: &'a syn::TypePath) {
-        assert!(i.qself.is_none()); // TODO: handle
-        self.visit_path(&i.path);
+        self.visit_qualified_path(&i.qself, &i.path);
     }
 
     fn visit_type_ptr(&mut self, i: &'a syn::TypePtr) {
-        unimplemented!();
+        if i.mutability.is_some() {
+            write!(self, "*mut ");
+        } else {
+            write!(self, "*const ");
+        }
+
+        self.visit_type_operand(&i.elem);
     }
 
     fn visit_type_reference(&mut self, i: &'a syn::TypeReference) {
@@ -1316,17 +2575,14 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
 
         if let Some(ref lt) = i.lifetime {
             self.visit_lifetime(lt);
+            write!(self, " ");
         }
 
         if i.mutability.is_some() {
-            if i.lifetime.is_some() {
-                write!(self, " ");
-            }
-
             write!(self, "mut ");
         }
 
-        self.visit_type(&i.elem);
+        self.visit_type_operand(&i.elem);
     }
 
     fn visit_type_slice(&mut self, i: &'a syn::TypeSlice) {
@@ -1336,17 +2592,22 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_type_trait_object(&mut self, i: &'a syn::TypeTraitObject) {
-        unimplemented!();
-    }
+        if i.dyn_token.is_some() {
+            write!(self, "dyn ");
+        }
 
-    fn visit_type_tuple(&mut self, i: &'a syn::TypeTuple) {
-        write!(self, "(");
-        self.visit_punctuated(&i.elems, SpaceRight);
-        write!(self, ")");
+        self.visit_punctuated(&i.bounds, SpaceBoth);
     }
 
-    fn visit_type_verbatim(&mut self, i: &'a syn::TypeVerbatim) {
-        unimplemented!();
+    fn visit_type_tuple(&mut self, i: &'a syn::TypeTuple) {
+        if i.elems.len() == 1 {
+            // A single element tuple requires the trailing comma
+            write!(self, "(");
+            self.visit_type(&i.elems[0]);
+            write!(self, ",)");
+        } else {
+            self.visit_list("(", ")", &i.elems);
+        }
     }
 
     fn visit_un_op(&mut self, i: &'a syn::UnOp) {
@@ -1362,6 +2623,9 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
             Neg(_) => {
                 write!(self, "-");
             }
+            _ => {
+                unimplemented!();
+            }
         }
     }
 
@@ -1370,7 +2634,7 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_use_group(&mut self, i: &'a syn::UseGroup) {
-        unimplemented!();
+        self.visit_list("{", "}", &i.items);
     }
 
     fn visit_use_path(&mut self, i: &'a syn::UsePath) {
@@ -1380,90 +2644,336 @@ impl<'a> syn::visit::Visit<'a> for FormatFile {
     }
 
     fn visit_use_rename(&mut self, i: &'a syn::UseRename) {
-        unimplemented!();
+        write!(self, "{} as {}", i.ident, i.rename);
     }
 
     fn visit_variant(&mut self, i: &'a syn::Variant) {
-        unimplemented!();
-    }
+        use syn::Fields::*;
 
-    fn visit_vis_crate(&mut self, i: &'a syn::VisCrate) {
-        unimplemented!();
+        self.visit_attributes(&i.attrs);
+        self.visit_ident(&i.ident);
+
+        match i.fields {
+            Named(ref fields_named) => {
+                write!(self, " ");
+                self.block_no_nl(|v| {
+                    for (pos, field) in fields_named.named.iter().enumerate() {
+                        v.visit_field(field);
+
+                        if pos + 1 < fields_named.named.len() {
+                            write!(v, ",");
+                        } else {
+                            v.visit_vertical_trailing_comma();
+                        }
+
+                        write!(v, "\n");
+                    }
+                });
+            }
+            Unnamed(ref fields_unnamed) => {
+                self.visit_fields_unnamed(fields_unnamed);
+            }
+            Unit => {}
+        }
+
+        if let Some((_, ref discriminant)) = i.discriminant {
+            write!(self, " = ");
+            self.visit_expr(discriminant);
+        }
     }
 
-    fn visit_vis_public(&mut self, i: &'a syn::VisPublic) {
-        unimplemented!();
+    fn visit_variadic(&mut self, i: &'a syn::Variadic) {
+        self.visit_inline_attributes(&i.attrs);
+
+        if let Some((ref pat, _)) = i.pat {
+            self.visit_pat(pat);
+            write!(self, ": ");
+        }
+
+        write!(self, "...");
     }
 
     fn visit_vis_restricted(&mut self, i: &'a syn::VisRestricted) {
-        unimplemented!();
+        write!(self, "pub(");
+
+        if i.in_token.is_some() {
+            write!(self, "in ");
+        }
+
+        self.visit_path(&i.path);
+        write!(self, ") ");
     }
 
     fn visit_visibility(&mut self, i: &'a syn::Visibility) {
         use syn::Visibility::*;
 
-        match i {
+        match *i {
             Public(_) => {
                 write!(self, "pub ");
             }
-            Inherited => {}
-            actual => {
-                unimplemented!("{:?}", actual);
+            Restricted(ref vis) => {
+                self.visit_vis_restricted(vis);
             }
+            Inherited => {}
         }
     }
 
     fn visit_where_clause(&mut self, i: &'a syn::WhereClause) {
-        write!(self, "where\n");
+        write!(self, "where");
 
         self.indent(|v| {
-            v.visit_punctuated(&i.predicates, NewLine);
+            write!(v, "\n");
+
+            for (pos, predicate) in i.predicates.iter().enumerate() {
+                v.visit_where_predicate(predicate);
+
+                if pos + 1 < i.predicates.len() {
+                    write!(v, ",");
+                } else {
+                    v.visit_vertical_trailing_comma();
+                }
+
+                write!(v, "\n");
+            }
         });
     }
 }
 
-fn is_inner_attr(attr: &syn::Attribute) -> bool {
-    use syn::AttrStyle::*;
+/// Whether the left and right operands of a binary expression need
+/// parentheses.
+fn operand_parens(i: &syn::ExprBinary) -> (bool, bool) {
+    let prec = Precedence::of_bin_op(&i.op);
+
+    // Comparison operators do not associate and compound assignment
+    // associates to the right
+    let (left, right) = match prec {
+        Precedence::Compare => {
+            (Precedence::of(&i.left) <= prec, Precedence::of(&i.right) <= prec)
+        }
+        Precedence::Assign => {
+            (Precedence::of(&i.left) <= prec, Precedence::of(&i.right) < prec)
+        }
+        _ => {
+            (Precedence::of(&i.left) < prec, Precedence::of(&i.right) <= prec)
+        }
+    };
 
-    match attr.style {
-        Inner(_) => true,
+    // `a as u8 < 3` would start generic arguments of `u8`
+    let opens_generics = match i.op {
+        syn::BinOp::Lt(_) | syn::BinOp::Shl(_) => ends_with_cast(&i.left),
+        _ => false,
+    };
+
+    (left || opens_generics, right)
+}
+
+/// Whether the operand of a prefix operator needs parentheses. A closure,
+/// `return`, `break` or `yield` does not, as nothing before it can be taken
+/// as part of it.
+fn prefix_operand_parens(expr: &syn::Expr) -> bool {
+    let prec = Precedence::of(expr);
+    prec < Precedence::Prefix && prec != Precedence::Any
+}
+
+/// Whether `expr` is written ending with the type of a cast
+fn ends_with_cast(expr: &syn::Expr) -> bool {
+    match *expr {
+        syn::Expr::Cast(_) => true,
+        syn::Expr::Group(ref group) => ends_with_cast(&group.expr),
+        syn::Expr::Binary(ref bin) => !operand_parens(bin).1 && ends_with_cast(&bin.right),
         _ => false,
     }
 }
 
-fn is_doc_attr(attr: &syn::Attribute) -> bool {
-    attr.path.segments.len() == 1 &&
-        attr.path.segments[0].ident == "doc"
+/// Whether `ty` is a trait object or `impl Trait` with more than one bound,
+/// looking through invisible groups.
+fn has_multiple_bounds(ty: &syn::Type) -> bool {
+    match *ty {
+        syn::Type::TraitObject(ref ty) => ty.bounds.len() > 1,
+        syn::Type::ImplTrait(ref ty) => ty.bounds.len() > 1,
+        syn::Type::Group(ref group) => has_multiple_bounds(&group.elem),
+        _ => false,
+    }
 }
 
-impl Write for FormatFile {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        let mut first = true;
-        let mut should_indent = self.is_start_of_line();
+/// Removes and returns the attributes of the leftmost operand of `expr`,
+/// where syn puts the attributes of a statement.
+fn take_leftmost_attrs(expr: &mut syn::Expr) -> Vec<syn::Attribute> {
+    use syn::Expr::*;
+
+    match *expr {
+        Assign(ref mut e) if e.attrs.is_empty() => take_leftmost_attrs(&mut e.left),
+        Binary(ref mut e) if e.attrs.is_empty() => take_leftmost_attrs(&mut e.left),
+        Cast(ref mut e) if e.attrs.is_empty() => take_leftmost_attrs(&mut e.expr),
+        _ => {
+            match expr_attrs_mut(expr) {
+                Some(attrs) => mem::take(attrs),
+                None => vec![],
+            }
+        }
+    }
+}
+
+/// The attributes of `expr`, if it can have any
+fn expr_attrs_mut(expr: &mut syn::Expr) -> Option<&mut Vec<syn::Attribute>> {
+    use syn::Expr::*;
 
-        for line in s.lines() {
-            if !first {
-                self.out.push_str("\n");
+    macro_rules! attrs {
+        ($($variant:ident),*) => {
+            match *expr {
+                $($variant(ref mut e) => Some(&mut e.attrs),)*
+                _ => None,
             }
+        }
+    }
+
+    attrs!(
+        Array, Assign, Async, Await, Binary, Block, Break, Call, Cast, Closure, Const, Continue,
+        Field, ForLoop, Group, If, Index, Infer, Let, Lit, Loop, Macro, Match, MethodCall, Paren,
+        Path, Range, RawAddr, Reference, Repeat, Return, Struct, Try, TryBlock, Tuple, Unary,
+        Unsafe, While, Yield
+    )
+}
+
+/// Whether `expr` is a field access, looking through invisible groups
+fn is_field(expr: &syn::Expr) -> bool {
+    match *expr {
+        syn::Expr::Field(_) => true,
+        syn::Expr::Group(ref group) => is_field(&group.expr),
+        _ => false,
+    }
+}
 
-            first = false;
+/// Whether `expr` is an integer literal, looking through invisible groups
+fn is_int_lit(expr: &syn::Expr) -> bool {
+    match *expr {
+        syn::Expr::Lit(ref lit) => matches!(lit.lit, syn::Lit::Int(_)),
+        syn::Expr::Group(ref group) => is_int_lit(&group.expr),
+        _ => false,
+    }
+}
+
+/// Whether `expr` contains a struct literal that is not inside of some
+/// delimiter. Such an expression needs parentheses in the head of an `if`,
+/// `while`, `match` or `for`, where the struct's `{` would open the body.
+fn has_exterior_struct(expr: &syn::Expr) -> bool {
+    use syn::Expr::*;
+
+    match *expr {
+        Struct(_) => true,
+        Group(ref e) => has_exterior_struct(&e.expr),
+        Assign(ref e) => has_exterior_struct(&e.left) || has_exterior_struct(&e.right),
+        Binary(ref e) => has_exterior_struct(&e.left) || has_exterior_struct(&e.right),
+        Unary(ref e) => has_exterior_struct(&e.expr),
+        Reference(ref e) => has_exterior_struct(&e.expr),
+        Cast(ref e) => has_exterior_struct(&e.expr),
+        Field(ref e) => has_exterior_struct(&e.base),
+        MethodCall(ref e) => has_exterior_struct(&e.receiver),
+        Index(ref e) => has_exterior_struct(&e.expr),
+        Await(ref e) => has_exterior_struct(&e.base),
+        Try(ref e) => has_exterior_struct(&e.expr),
+        Range(ref e) => {
+            e.start.as_ref().is_some_and(|e| has_exterior_struct(e)) ||
+                e.end.as_ref().is_some_and(|e| has_exterior_struct(e))
+        }
+        _ => false,
+    }
+}
 
-            let do_indent = should_indent &&
-                !line.is_empty() &&
-                line.as_bytes()[0] != b'\n';
+/// Returns the text of `expr` if it is a literal that fits in a column of an
+/// array table.
+fn short_literal(expr: &syn::Expr) -> Option<String> {
+    let text = match *expr {
+        syn::Expr::Lit(ref lit) if lit.attrs.is_empty() => {
+            lit.lit.to_token_stream().to_string()
+        }
+        syn::Expr::Unary(ref unary) if unary.attrs.is_empty() => {
+            let is_neg = matches!(unary.op, syn::UnOp::Neg(_));
 
-            if do_indent {
-                self.push_spaces();
+            match *unary.expr {
+                syn::Expr::Lit(ref lit) if is_neg && lit.attrs.is_empty() => {
+                    format!("-{}", lit.lit.to_token_stream())
+                }
+                _ => return None,
             }
+        }
+        _ => return None,
+    };
+
+    if text.chars().count() <= SHORT_LITERAL_WIDTH && !text.contains('\n') {
+        Some(text)
+    } else {
+        None
+    }
+}
+
+fn is_inner_attr(attr: &syn::Attribute) -> bool {
+    use syn::AttrStyle::*;
+
+    matches!(attr.style, Inner(_))
+}
+
+fn is_import(item: &syn::Item) -> bool {
+    matches!(*item, syn::Item::Use(_) | syn::Item::ExternCrate(_))
+}
+
+fn is_item_macro(item: &syn::Item) -> bool {
+    matches!(*item, syn::Item::Macro(_))
+}
+
+fn is_pat_rest(pat: &syn::Pat) -> bool {
+    matches!(*pat, syn::Pat::Rest(_))
+}
+
+fn is_doc_attr(attr: &syn::Attribute) -> bool {
+    attr.path().is_ident("doc")
+}
 
-            // If this loops again, then we just wrote a new line
-            should_indent = true;
+/// Returns the doc string if the attribute can be written as a doc comment.
+///
+/// Only `#[doc = "..."]` qualifies, anything else, such as `#[doc(hidden)]`,
+/// is written as a regular attribute. So is an outer doc with a line
+/// starting with `/`, as `////` is an ordinary comment.
+fn doc_comment(attr: &syn::Attribute) -> Option<String> {
+    if !is_doc_attr(attr) {
+        return None;
+    }
+
+    match attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(ref s), .. }),
+            ..
+        }) => {
+            let doc = s.value();
+
+            if !is_inner_attr(attr) && doc.lines().any(|line| line.starts_with('/')) {
+                return None;
+            }
 
-            self.out.push_str(line);
+            Some(doc)
         }
+        _ => None,
+    }
+}
+
+impl FormatFile {
+    /// Called by `write!`. Takes precedence over `Write::write_fmt`, so that
+    /// callers do not have to handle a result that is always `Ok`.
+    fn write_fmt(&mut self, args: fmt::Arguments) {
+        Write::write_fmt(self, args).expect("writing to the printer does not fail");
+    }
+}
+
+impl Write for FormatFile {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for (pos, line) in s.split('\n').enumerate() {
+            if pos > 0 {
+                self.pp.hardbreak();
+            }
 
-        if s.as_bytes().last() == Some(&b'\n') {
-            self.out.push_str("\n");
+            if !line.is_empty() {
+                self.pp.text(line);
+            }
         }
 
         Ok(())
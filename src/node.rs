@@ -15,15 +15,19 @@ macro_rules! impl_node {
     }
 }
 
+impl_node!(BareFnArg, visit_bare_fn_arg);
 impl_node!(Expr, visit_expr);
 impl_node!(Field, visit_field);
+impl_node!(FieldPat, visit_field_pat);
 impl_node!(FieldValue, visit_field_value);
 impl_node!(FnArg, visit_fn_arg);
 impl_node!(GenericArgument, visit_generic_argument);
 impl_node!(GenericParam, visit_generic_param);
-impl_node!(NestedMeta, visit_nested_meta);
+impl_node!(Lifetime, visit_lifetime);
+impl_node!(Meta, visit_meta);
 impl_node!(Pat, visit_pat);
 impl_node!(PathSegment, visit_path_segment);
 impl_node!(Type, visit_type);
 impl_node!(TypeParamBound, visit_type_param_bound);
+impl_node!(UseTree, visit_use_tree);
 impl_node!(WherePredicate, visit_where_predicate);
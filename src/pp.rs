@@ -0,0 +1,346 @@
+//! A pretty printer in the style of Oppen's algorithm.
+//!
+//! The visitor writes text, breaks and groups into a `Printer`. Once the
+//! whole file has been written, each group is laid out on a single line if
+//! it fits within the maximum width, otherwise its breaks become newlines.
+
+use Config;
+
+use std::cmp;
+
+/// How the breaks of a group are laid out when the group does not fit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Breaks {
+    /// Every break in the group starts a new line
+    Consistent,
+    /// Only the breaks needed to stay within the width start a new line
+    Inconsistent,
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    /// A space of `blank` columns, or a newline if the group is broken
+    Break { blank: usize },
+    /// An unconditional newline
+    Hard,
+    /// Text that is only written if the enclosing group is broken, such as a
+    /// trailing comma.
+    IfBroken(String),
+    /// Opens a group, indenting lines that start inside of it by `indent`
+    /// levels if it is broken. The group is broken if it is wider than
+    /// `limit`, even if it would fit on the line, and always if it directly
+    /// holds a `Hard`.
+    Begin { indent: usize, breaks: Breaks, limit: Option<usize> },
+    End,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Mode {
+    Flat,
+    Broken(Breaks),
+}
+
+struct Frame {
+    indent: usize,
+    mode: Mode,
+}
+
+#[derive(Debug)]
+pub struct Printer {
+    tokens: Vec<Token>,
+}
+
+impl Printer {
+    pub fn new() -> Printer {
+        Printer { tokens: vec![] }
+    }
+
+    /// Writes `s`, which may only contain newlines if they are part of a
+    /// token, such as a multi-line string literal.
+    pub fn text(&mut self, s: &str) {
+        if let Some(&mut Token::Text(ref mut prev)) = self.tokens.last_mut() {
+            prev.push_str(s);
+            return;
+        }
+
+        self.tokens.push(Token::Text(s.to_string()));
+    }
+
+    pub fn hardbreak(&mut self) {
+        self.tokens.push(Token::Hard);
+    }
+
+    /// A break that is a single space when the group fits
+    pub fn space(&mut self) {
+        self.tokens.push(Token::Break { blank: 1 });
+    }
+
+    /// A break that is empty when the group fits
+    pub fn zerobreak(&mut self) {
+        self.tokens.push(Token::Break { blank: 0 });
+    }
+
+    pub fn if_broken(&mut self, s: &str) {
+        self.tokens.push(Token::IfBroken(s.to_string()));
+    }
+
+    pub fn begin(&mut self, indent: usize, breaks: Breaks) {
+        self.tokens.push(Token::Begin { indent, breaks, limit: None });
+    }
+
+    /// Like `begin`, but the group is broken if it is wider than `limit`
+    pub fn begin_limited(&mut self, indent: usize, breaks: Breaks, limit: usize) {
+        self.tokens.push(Token::Begin { indent, breaks, limit: Some(limit) });
+    }
+
+    pub fn end(&mut self) {
+        self.tokens.push(Token::End);
+    }
+
+    /// Returns a position that can be passed to `truncate`
+    pub fn mark(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Drops everything written since `mark` was called
+    pub fn truncate(&mut self, mark: usize) {
+        self.tokens.truncate(mark);
+    }
+
+    /// Lays out the written tokens according to `config`
+    pub fn print(self, config: &Config) -> String {
+        let sizes = self.sizes();
+        let max_width = config.max_width;
+        let newline = config.newline_style.as_str();
+
+        let mut out = String::new();
+        let mut col = 0;
+        let mut pending_indent = true;
+        let mut frames: Vec<Frame> = vec![];
+
+        // The indentation in effect when the first group on an empty line
+        // was opened, which is the line's indentation even if that group is
+        // broken.
+        let mut line_indent = None;
+
+        for (token, &size) in self.tokens.iter().zip(sizes.iter()) {
+            let frame_indent = frames.last().map(|f| f.indent).unwrap_or(0);
+            let indent = match line_indent {
+                Some(indent) if pending_indent => indent,
+                _ => frame_indent,
+            };
+
+            // Lines are only indented once there is text on them, by the
+            // indentation in effect at that point.
+            let start = if pending_indent { indent * config.indent_width } else { col };
+            let remaining = max_width.saturating_sub(start);
+
+            let text = match *token {
+                Token::Text(ref s) => Some(s),
+                Token::IfBroken(ref s) => {
+                    match frames.last().map(|f| f.mode) {
+                        Some(Mode::Broken(_)) => Some(s),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            match *token {
+                Token::Text(_) | Token::IfBroken(_) => {
+                    let s = match text {
+                        Some(s) => s,
+                        None => continue,
+                    };
+
+                    if pending_indent {
+                        if config.hard_tabs {
+                            for _ in 0..indent {
+                                out.push('\t');
+                            }
+                        } else {
+                            for _ in 0..start {
+                                out.push(' ');
+                            }
+                        }
+
+                        col = start;
+                        pending_indent = false;
+                    }
+
+                    out.push_str(s);
+
+                    col = match s.rfind('\n') {
+                        Some(idx) => s[idx + 1..].chars().count(),
+                        None => col + s.chars().count(),
+                    };
+                }
+                Token::Break { blank } => {
+                    let mode = frames.last().map(|f| f.mode).unwrap_or(Mode::Flat);
+
+                    let is_newline = match mode {
+                        Mode::Flat => false,
+                        Mode::Broken(Breaks::Consistent) => true,
+                        Mode::Broken(Breaks::Inconsistent) => size > remaining,
+                    };
+
+                    if is_newline {
+                        out.push_str(newline);
+                        col = 0;
+                        pending_indent = true;
+                        line_indent = None;
+                    } else if !pending_indent {
+                        for _ in 0..blank {
+                            out.push(' ');
+                        }
+
+                        col += blank;
+                    }
+                }
+                Token::Hard => {
+                    out.push_str(newline);
+                    col = 0;
+                    pending_indent = true;
+                    line_indent = None;
+                }
+                Token::Begin { indent: offset, breaks, limit } => {
+                    let fits = size <= remaining && limit.map(|l| size <= l).unwrap_or(true);
+
+                    if pending_indent && line_indent.is_none() {
+                        line_indent = Some(frame_indent);
+                    }
+
+                    // A group on one line leaves the indentation of blocks
+                    // inside of it alone
+                    frames.push(if fits {
+                        Frame { indent: frame_indent, mode: Mode::Flat }
+                    } else {
+                        Frame { indent: frame_indent + offset, mode: Mode::Broken(breaks) }
+                    });
+                }
+                Token::End => {
+                    frames.pop();
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Computes the width each token needs if it is laid out on one line.
+    ///
+    /// For a group this is its width up to the end of the group, including
+    /// any text directly following it. For a break it is the width up to the
+    /// next break in the same group, including text that is only written
+    /// because the group is broken. Either stops at the first hard newline,
+    /// so a group holding a block only needs its first line to fit. A group
+    /// that directly holds a hard newline can never be laid out on one line
+    /// and gets `usize::MAX`.
+    fn sizes(&self) -> Vec<usize> {
+        let len = self.tokens.len();
+
+        // Width of everything before each token, without and with the text
+        // of broken groups
+        let mut offsets = Vec::with_capacity(len + 1);
+        let mut broken_offsets = Vec::with_capacity(len + 1);
+        let mut total = 0;
+        let mut broken_total = 0;
+
+        for token in &self.tokens {
+            offsets.push(total);
+            broken_offsets.push(broken_total);
+
+            let (width, broken_width) = match *token {
+                Token::Text(ref s) => {
+                    let width = s.split('\n').next().unwrap().chars().count();
+                    (width, width)
+                }
+                Token::Break { blank } => (blank, blank),
+                Token::IfBroken(ref s) => (0, s.chars().count()),
+                _ => (0, 0),
+            };
+
+            total += width;
+            broken_total += broken_width;
+        }
+
+        offsets.push(total);
+        broken_offsets.push(broken_total);
+
+        // Index of the first hard newline at or after each token
+        let mut next_hard = vec![len; len + 1];
+
+        for idx in (0..len).rev() {
+            next_hard[idx] = match self.tokens[idx] {
+                Token::Hard => idx,
+                _ => next_hard[idx + 1],
+            };
+        }
+
+        let mut sizes = vec![0; len];
+
+        // Open groups, with the break that is waiting for the next one and
+        // whether the group holds a hard newline
+        let mut stack: Vec<(usize, Option<usize>, bool)> = vec![];
+        let mut top_break = None;
+
+        let measure = |from: usize, to: usize| {
+            offsets[cmp::min(to, next_hard[from])] - offsets[from]
+        };
+        let measure_broken = |from: usize, to: usize| {
+            broken_offsets[cmp::min(to, next_hard[from])] - broken_offsets[from]
+        };
+
+        for (idx, token) in self.tokens.iter().enumerate() {
+            match *token {
+                Token::Text(ref s) => {
+                    sizes[idx] = s.chars().count();
+                }
+                Token::Break { .. } => {
+                    if let Some(prev) = top_break {
+                        sizes[prev] = measure_broken(prev, idx);
+                    }
+
+                    top_break = Some(idx);
+                }
+                Token::Begin { .. } => {
+                    stack.push((idx, top_break.take(), false));
+                }
+                Token::End => {
+                    if let Some(prev) = top_break {
+                        sizes[prev] = measure_broken(prev, idx);
+                    }
+
+                    // The closing delimiter must fit on the line as well
+                    let to = match self.tokens.get(idx + 1) {
+                        Some(&Token::Text(_)) => idx + 2,
+                        _ => idx,
+                    };
+
+                    if let Some((begin, outer_break, hard)) = stack.pop() {
+                        sizes[begin] = if hard { usize::MAX } else { measure(begin, to) };
+                        top_break = outer_break;
+                    }
+                }
+                Token::Hard => {
+                    if let Some(top) = stack.last_mut() {
+                        top.2 = true;
+                    }
+                }
+                Token::IfBroken(_) => {}
+            }
+        }
+
+        // Breaks outside of any group and groups left open
+        if let Some(prev) = top_break {
+            sizes[prev] = measure_broken(prev, len);
+        }
+
+        for (begin, _, _) in stack {
+            sizes[begin] = measure(begin, len);
+        }
+
+        sizes
+    }
+}
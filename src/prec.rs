@@ -0,0 +1,72 @@
+use syn;
+
+/// Operator precedence, from loosest to tightest binding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Any,
+    Assign,
+    Range,
+    Or,
+    And,
+    Let,
+    Compare,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Arithmetic,
+    Term,
+    Cast,
+    Prefix,
+    Postfix,
+}
+
+impl Precedence {
+    pub fn of(expr: &syn::Expr) -> Precedence {
+        use syn::Expr::*;
+
+        match *expr {
+            Unary(ref e) => Precedence::of_prefix(&e.expr),
+            Reference(ref e) => Precedence::of_prefix(&e.expr),
+            RawAddr(ref e) => Precedence::of_prefix(&e.expr),
+            Cast(_) => Precedence::Cast,
+            Binary(ref e) => Precedence::of_bin_op(&e.op),
+            Let(_) => Precedence::Let,
+            Assign(_) => Precedence::Assign,
+            Range(_) => Precedence::Range,
+            Closure(_) | Return(_) | Break(_) | Yield(_) => Precedence::Any,
+            // Invisible groups bind as loosely as the expression they wrap
+            Group(ref e) => Precedence::of(&e.expr),
+            _ => Precedence::Postfix,
+        }
+    }
+
+    /// A prefix operator applied to a closure, `return`, `break` or `yield`
+    /// binds as loosely as its operand, which takes in everything after it.
+    fn of_prefix(operand: &syn::Expr) -> Precedence {
+        match Precedence::of(operand) {
+            Precedence::Any => Precedence::Any,
+            _ => Precedence::Prefix,
+        }
+    }
+
+    pub fn of_bin_op(op: &syn::BinOp) -> Precedence {
+        use syn::BinOp::*;
+
+        match *op {
+            Add(_) | Sub(_) => Precedence::Arithmetic,
+            Mul(_) | Div(_) | Rem(_) => Precedence::Term,
+            And(_) => Precedence::And,
+            Or(_) => Precedence::Or,
+            BitXor(_) => Precedence::BitXor,
+            BitAnd(_) => Precedence::BitAnd,
+            BitOr(_) => Precedence::BitOr,
+            Shl(_) | Shr(_) => Precedence::Shift,
+            Eq(_) | Lt(_) | Le(_) | Ne(_) | Ge(_) | Gt(_) => Precedence::Compare,
+            AddAssign(_) | SubAssign(_) | MulAssign(_) | DivAssign(_) | RemAssign(_) |
+            BitXorAssign(_) | BitAndAssign(_) | BitOrAssign(_) | ShlAssign(_) |
+            ShrAssign(_) => Precedence::Assign,
+            _ => unimplemented!(),
+        }
+    }
+}
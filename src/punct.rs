@@ -1,27 +1,28 @@
 use syn::token;
 
+// The names read as spacing around a punctuation, as in `SpaceBoth`
+#[allow(clippy::enum_variant_names)]
 pub enum Space {
     NoSpace,
     SpaceBoth,
     SpaceRight,
-    NewLine,
 }
 
 pub trait Punctuation {
-    fn as_str(&self) -> &str;
+    fn as_str(&self) -> &'static str;
 }
 
 macro_rules! impl_punct {
     ($t:ident, $s:expr) => {
         impl Punctuation for token::$t {
-            fn as_str(&self) -> &str {
+            fn as_str(&self) -> &'static str {
                 $s
             }
         }
     }
 }
 
-impl_punct!(Add, "+");
+impl_punct!(Plus, "+");
 impl_punct!(Comma, ",");
-impl_punct!(Colon2, "::");
+impl_punct!(PathSep, "::");
 impl_punct!(Or, "|");
@@ -0,0 +1,362 @@
+use proc_macro2::{Delimiter, Punct, Spacing, TokenStream, TokenTree};
+
+/// Writes a token stream on a single line, with spacing normalized to
+/// roughly match how the tokens would be written by hand.
+pub fn normalize(tts: TokenStream) -> String {
+    let mut out = String::new();
+    write_stream(&mut out, tts);
+    out
+}
+
+/// Like `normalize`, but starts a new line after each top level `;`. Each
+/// line has its trailing whitespace removed.
+pub fn normalize_lines(tts: TokenStream) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = vec![];
+
+    for tt in tts {
+        let is_semi = match tt {
+            TokenTree::Punct(ref punct) => punct.as_char() == ';',
+            _ => false,
+        };
+
+        line.push(tt);
+
+        if is_semi {
+            lines.push(normalize(line.drain(..).collect()).trim_end().to_string());
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(normalize(line.into_iter().collect()).trim_end().to_string());
+    }
+
+    lines
+}
+
+/// The kind of the previously written token, used to decide on spacing
+#[derive(Copy, Clone, PartialEq)]
+enum Prev {
+    Start,
+    Ident,
+    Literal,
+    Group,
+    /// An operator that is followed by a space
+    Spaced,
+    /// An operator that binds to the next token
+    Tight,
+}
+
+/// Operators made of more than one punctuation character. Joint punctuation
+/// is only glued together when it forms one of these.
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "...", "..=", "::", "->", "=>", "==", "!=", "<=", ">=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "^=", "&=", "|=", "<<", ">>", "..",
+];
+
+/// Fragment specifiers of `macro_rules!` metavariables, as in `$x:expr`
+const FRAGMENTS: &[&str] = &[
+    "block", "expr", "ident", "item", "lifetime", "literal", "meta", "pat", "pat_param", "path",
+    "stmt", "tt", "ty", "vis",
+];
+
+fn write_stream(out: &mut String, tts: TokenStream) {
+    let tts: Vec<TokenTree> = tts.into_iter().collect();
+    let mut prev = Prev::Start;
+    let mut pos = 0;
+
+    // Tracks `<` that were taken to open generic arguments, such as in
+    // `Vec<u8>` or `::<T>`.
+    let mut generics = 0;
+    let mut is_type_name = false;
+    let mut is_dyn = false;
+    let mut is_move = false;
+    let mut is_metavar = false;
+    let mut in_closure_params = false;
+
+    // Set after a `$(...)` or `#(...)` repetition, and after the separator
+    // that follows one, as in `$(...),*`.
+    let mut is_repetition = false;
+
+    // The previous operator if it was not joined to what follows, such as
+    // the first `&` in `& &x`.
+    let mut alone: Option<String> = None;
+
+    while pos < tts.len() {
+        let tt = tts[pos].clone();
+        pos += 1;
+
+        match tt {
+            TokenTree::Ident(ident) => {
+                let ident = ident.to_string();
+
+                is_metavar = prev == Prev::Tight && out.ends_with('$');
+                space(out, prev);
+                out.push_str(&ident);
+                prev = Prev::Ident;
+                is_type_name = ident.starts_with(char::is_uppercase);
+                is_dyn = ident == "dyn";
+                is_move = ident == "move";
+                is_repetition = false;
+                alone = None;
+            }
+            TokenTree::Literal(lit) => {
+                space(out, prev);
+                out.push_str(&lit.to_string());
+                prev = Prev::Literal;
+                is_repetition = false;
+                alone = None;
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::None => ("", ""),
+                };
+
+                // Calls, indexing and macro invocations hug the preceding token
+                let tight = match group.delimiter() {
+                    Delimiter::Parenthesis | Delimiter::Bracket => {
+                        prev == Prev::Ident || prev == Prev::Group
+                    }
+                    _ => false,
+                };
+
+                if !tight {
+                    space(out, prev);
+                }
+
+                is_repetition = group.delimiter() == Delimiter::Parenthesis &&
+                    prev == Prev::Tight &&
+                    (out.ends_with('$') || out.ends_with('#'));
+
+                let inner = normalize(group.stream());
+
+                out.push_str(open);
+
+                if group.delimiter() == Delimiter::Brace && !inner.is_empty() {
+                    out.push(' ');
+                    out.push_str(&inner);
+                    out.push(' ');
+                } else {
+                    out.push_str(&inner);
+                }
+
+                out.push_str(close);
+                prev = Prev::Group;
+                alone = None;
+            }
+            TokenTree::Punct(punct) => {
+                let op = take_operator(&tts, &mut pos, &punct);
+
+                // Keep punctuation that was written apart from splitting
+                // differently when the output is parsed again, as `& &x`
+                // would become `&&x`.
+                if let (Some(ref last), Prev::Tight) = (alone.take(), prev) {
+                    if joins_operator(last, &op) {
+                        out.push(' ');
+                    }
+                }
+
+                let is_operand = match prev {
+                    Prev::Ident => !is_move,
+                    Prev::Literal | Prev::Group => true,
+                    _ => false,
+                };
+
+                let is_repetition_op = is_repetition && matches!(&op[..], "," | "*" | "+" | "?");
+
+                if is_repetition_op {
+                    // The separator and operator of a repetition hug it
+                    out.push_str(&op);
+                    prev = Prev::Spaced;
+                } else if op == "*" && prev == Prev::Ident && is_dyn {
+                    // `dyn* Trait`
+                    out.push_str(&op);
+                    prev = Prev::Spaced;
+                } else if op == ":" && is_metavar && is_fragment(tts.get(pos)) {
+                    // `$x:expr`
+                    out.push_str(&op);
+                    prev = Prev::Tight;
+                } else if op == "<" && opens_generics(out, prev, is_type_name, &tts[pos..]) {
+                    generics += 1;
+                    out.push_str(&op);
+                    prev = Prev::Tight;
+                } else if generics >= op.len() && op.chars().all(|c| c == '>') {
+                    generics -= op.len();
+                    out.push_str(&op);
+                    prev = Prev::Group;
+                } else if op == "|" && in_closure_params {
+                    in_closure_params = false;
+                    out.push_str(&op);
+                    prev = Prev::Spaced;
+                } else if op == "|" && !is_operand {
+                    // Closure parameters
+                    in_closure_params = true;
+                    space(out, prev);
+                    out.push_str(&op);
+                    prev = Prev::Tight;
+                } else {
+                    prev = write_op(out, &op, prev);
+                }
+
+                is_move = false;
+                is_metavar = false;
+                is_repetition = is_repetition_op && op == ",";
+                alone = match punct_at(&tts, pos - 1) {
+                    Some(last) if last.spacing() == Spacing::Alone => Some(op),
+                    _ => None,
+                };
+            }
+        }
+    }
+}
+
+/// Takes the longest known operator starting with `first`, advancing `pos`
+/// past any further characters that belong to it.
+fn take_operator(tts: &[TokenTree], pos: &mut usize, first: &Punct) -> String {
+    let mut run = first.as_char().to_string();
+    let mut spacing = first.spacing();
+    let mut end = *pos;
+
+    while spacing == Spacing::Joint && run.len() < 3 {
+        match punct_at(tts, end) {
+            Some(next) => {
+                run.push(next.as_char());
+                spacing = next.spacing();
+                end += 1;
+            }
+            None => break,
+        }
+    }
+
+    while run.len() > 1 && !OPERATORS.contains(&&run[..]) {
+        run.pop();
+    }
+
+    *pos += run.len() - 1;
+    run
+}
+
+/// Whether `op` written right after `last` would lex as a different
+/// operator, as `..` followed by `=` would become `..=`.
+fn joins_operator(last: &str, op: &str) -> bool {
+    let joined = format!("{}{}", last, op);
+
+    (last.len() + 1..=joined.len()).any(|len| OPERATORS.contains(&&joined[..len]))
+}
+
+/// Whether a `<` opens generic arguments. After `::` it always does.
+fn opens_generics(out: &str, prev: Prev, is_type_name: bool, rest: &[TokenTree]) -> bool {
+    out.ends_with("::") || prev == Prev::Ident && is_type_name && closes_before_end(rest)
+}
+
+/// Whether a `<` after a type name is matched by a `>` before anything that
+/// marks it as a comparison, as in `assert!(MAX < LIMIT && x > 0)`.
+fn closes_before_end(tts: &[TokenTree]) -> bool {
+    let mut depth = 1;
+
+    for (pos, tt) in tts.iter().enumerate() {
+        let punct = match *tt {
+            TokenTree::Punct(ref punct) => punct,
+            _ => continue,
+        };
+
+        let next = match punct_at(tts, pos + 1) {
+            Some(next) if punct.spacing() == Spacing::Joint => Some(next.as_char()),
+            _ => None,
+        };
+
+        match (punct.as_char(), next) {
+            (';', _) | ('&', Some('&')) | ('|', Some('|')) | ('=', Some('=')) | ('!', Some('=')) |
+            ('=', Some('>')) => return false,
+            ('<', _) => depth += 1,
+            ('>', _) => {
+                // The `>` of `->` does not close anything
+                let is_arrow = pos > 0 && match punct_at(tts, pos - 1) {
+                    Some(prev) => prev.spacing() == Spacing::Joint && prev.as_char() == '-',
+                    None => false,
+                };
+
+                if !is_arrow {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        return true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+fn is_fragment(tt: Option<&TokenTree>) -> bool {
+    match tt {
+        Some(TokenTree::Ident(ident)) => FRAGMENTS.iter().any(|f| ident == f),
+        _ => false,
+    }
+}
+
+fn punct_at(tts: &[TokenTree], pos: usize) -> Option<&Punct> {
+    match tts.get(pos) {
+        Some(TokenTree::Punct(punct)) => Some(punct),
+        _ => None,
+    }
+}
+
+fn write_op(out: &mut String, op: &str, prev: Prev) -> Prev {
+    let is_operand = matches!(prev, Prev::Ident | Prev::Literal | Prev::Group);
+
+    match op {
+        "," | ";" | ":" => {
+            out.push_str(op);
+            Prev::Spaced
+        }
+        "." | ".." | "..=" | "::" | "?" => {
+            if prev == Prev::Spaced {
+                space(out, prev);
+            }
+
+            out.push_str(op);
+            Prev::Tight
+        }
+        "!" if prev == Prev::Ident => {
+            // Macro invocation
+            out.push_str(op);
+            Prev::Tight
+        }
+        "'" | "#" | "$" | "!" => {
+            space(out, prev);
+            out.push_str(op);
+            Prev::Tight
+        }
+        "&" | "*" | "-" | "&&" if !is_operand => {
+            // Unary operators bind to their operand
+            space(out, prev);
+            out.push_str(op);
+            Prev::Tight
+        }
+        _ => {
+            space(out, prev);
+            out.push_str(op);
+            out.push(' ');
+            Prev::Spaced
+        }
+    }
+}
+
+fn space(out: &mut String, prev: Prev) {
+    match prev {
+        Prev::Ident | Prev::Literal | Prev::Group => out.push(' '),
+        Prev::Spaced => {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+        }
+        Prev::Start | Prev::Tight => {}
+    }
+}
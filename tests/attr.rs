@@ -0,0 +1,332 @@
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn meta_attributes() {
+    check! {
+        r#"
+#[derive(Debug, Clone)]
+#[serde(with = "a::b")]
+#[cfg(all(unix, feature = "foo"))]
+struct Foo;
+"#,
+        #[derive(Debug, Clone)]
+        #[serde(with = "a::b")]
+        #[cfg(all(unix, feature = "foo"))]
+        struct Foo;
+    }
+}
+
+#[test]
+fn path_attributes() {
+    check! {
+        r#"
+#[rustfmt::skip]
+#[tokio::main(flavor = "current_thread")]
+#[::foo::bar]
+struct Foo;
+"#,
+        #[rustfmt::skip]
+        #[tokio::main(flavor = "current_thread")]
+        #[::foo::bar]
+        struct Foo;
+    }
+}
+
+#[test]
+fn token_attributes() {
+    check! {
+        r#"
+#[foo = 1 + 2]
+#[bar(a::b, c = &x)]
+#[baz(Vec::new(), [1, 2], -1)]
+struct Foo;
+"#,
+        #[foo = 1 + 2]
+        #[bar(a::b, c = &x)]
+        #[baz(Vec::new(), [1, 2], -1)]
+        struct Foo;
+    }
+}
+
+#[test]
+fn doc_comments() {
+    check! {
+        r#"
+/// One line
+///
+/// Another line
+struct Foo;
+"#,
+        /// One line
+        ///
+        /// Another line
+        struct Foo;
+    }
+
+    check! {
+        r#"
+//! Crate docs
+/// First
+/// Second
+use std::io;
+"#,
+        #![doc = " Crate docs"]
+        #[doc = " First\n Second"]
+        use std::io;
+    }
+
+    check! {
+        r#"
+impl Foo {
+    /// Creates a new `Foo`.
+    ///
+    /// Returns `None` on failure.
+    fn new() -> Option<Foo> {
+        None
+    }
+}
+"#,
+        impl Foo {
+            #[doc = " Creates a new `Foo`.\n\n Returns `None` on failure."]
+            fn new() -> Option<Foo> {
+                None
+            }
+        }
+    }
+}
+
+#[test]
+fn block_doc_comments() {
+    check! {
+        // The trailing space of the block is kept
+        "\n/// Block docs\n/// over two lines \nstruct Foo;\n",
+        /** Block docs
+ over two lines */
+        struct Foo;
+    }
+}
+
+#[test]
+fn doc_attributes() {
+    check! {
+        r#"
+#[doc(hidden)]
+#[doc(alias = "bar")]
+#[doc = concat!("a", "b")]
+struct Foo;
+"#,
+        #[doc(hidden)]
+        #[doc(alias = "bar")]
+        #[doc = concat!("a", "b")]
+        struct Foo;
+    }
+    check! {
+        "\n#[doc = \"/ path\"]\n/// Hard  \n/// break\nstruct Foo;\n",
+        #[doc = "/ path"]
+        #[doc = " Hard  \n break"]
+        struct Foo;
+    }
+}
+
+#[test]
+fn inner_attributes() {
+    check! {
+        r#"
+mod m {
+    #![allow(dead_code)]
+    use std::io;
+}
+"#,
+        mod m {
+            #![allow(dead_code)]
+            use std::io;
+        }
+    }
+
+    check! {
+        r#"
+#[inline]
+fn foo() {
+    #![allow(unused)]
+    let a = unsafe {
+        #![allow(unused_unsafe)]
+        bar()
+    };
+    let b = {
+        #![cfg(x)]
+        a
+    };
+    match b {
+        #![deny(unreachable_patterns)]
+        _ => (),
+    }
+}
+"#,
+        #[inline]
+        fn foo() {
+            #![allow(unused)]
+            let a = unsafe {
+                #![allow(unused_unsafe)]
+                bar()
+            };
+            let b = {
+                #![cfg(x)]
+                a
+            };
+            match b {
+                #![deny(unreachable_patterns)]
+                _ => (),
+            }
+        }
+    }
+}
+
+#[test]
+fn inner_attributes_in_traits_and_extern_blocks() {
+    check! {
+        r#"
+trait Foo {
+    fn foo() {
+        #![allow(unused)]
+        bar()
+    }
+}
+"#,
+        trait Foo {
+            fn foo() {
+                #![allow(unused)]
+                bar()
+            }
+        }
+    }
+
+    check! {
+        r#"
+#[link(name = "c")]
+extern "C" {
+    #![allow(improper_ctypes)]
+    fn abs(input: i32) -> i32;
+}
+"#,
+        #[link(name = "c")]
+        extern "C" {
+            #![allow(improper_ctypes)]
+            fn abs(input: i32) -> i32;
+        }
+    }
+}
+
+#[test]
+fn expression_attributes() {
+    check! {
+        r#"
+fn foo() {
+    #[allow(unused_must_use)]
+    bar(#[allow(unused)] x, y);
+    let a = #[rustfmt::skip] |x| x + 1;
+    let b = Foo {
+        #[cfg(x)]
+        a: 1,
+    };
+    match c {
+        #[cfg(x)]
+        Foo { a, .. } => [#[cfg(x)] 1, 2],
+        _ => #[allow(unreachable_code)] (),
+    }
+}
+"#,
+        fn foo() {
+            #[allow(unused_must_use)]
+            bar(#[allow(unused)] x, y);
+            let a = #[rustfmt::skip] |x| x + 1;
+            let b = Foo {
+                #[cfg(x)]
+                a: 1,
+            };
+            match c {
+                #[cfg(x)]
+                Foo { a, .. } => [#[cfg(x)] 1, 2],
+                _ => #[allow(unreachable_code)] (),
+            }
+        }
+    }
+}
+
+#[test]
+fn closure_param_attributes() {
+    check! {
+        r#"
+fn foo() {
+    let a = |#[cfg(x)] a, #[allow(z)] _| a;
+    let b = |#[cfg(x)] (a, b), #[cfg(y)] &c: &u8| a;
+}
+"#,
+        fn foo() {
+            let a = |#[cfg(x)] a, #[allow(z)] _| a;
+            let b = |#[cfg(x)] (a, b), #[cfg(y)] &c: &u8| a;
+        }
+    }
+}
+
+#[test]
+fn statement_attributes_on_operands() {
+    check! {
+        r#"
+fn foo() {
+    #[cfg(a)]
+    x += 1;
+    #[cfg(a)]
+    x = 1;
+    #[cfg(a)]
+    a + b;
+    #[cfg(a)]
+    a * b + c;
+    #[allow(unused)]
+    x as u8;
+    let y = #[cfg(a)] x + 1;
+}
+"#,
+        fn foo() {
+            #[cfg(a)] x += 1;
+            #[cfg(a)] x = 1;
+            #[cfg(a)] a + b;
+            #[cfg(a)] a * b + c;
+            #[allow(unused)] x as u8;
+            let y = #[cfg(a)] x + 1;
+        }
+    }
+}
+
+#[test]
+fn long_attribute_lists() {
+    check! {
+        r#"
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    Serialize,
+    Deserialize,
+    Display,
+)]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[doc(alias = "bar")]
+struct Foo;
+"#,
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize, Display)]
+        #[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+        #[doc(alias = "bar")]
+        struct Foo;
+    }
+}
@@ -0,0 +1,204 @@
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use minifmt::{Config, NewlineStyle, TrailingComma};
+
+#[test]
+fn default_config() {
+    let tts = quote!(fn foo() { bar() });
+
+    assert_eq!(Config::new().fmt(tts.clone()).unwrap(), minifmt::fmt(tts).unwrap());
+}
+
+#[test]
+fn indent_width() {
+    let out = Config::new()
+        .indent_width(2)
+        .fmt(quote!(mod foo { fn bar() { baz() } }))
+        .unwrap();
+
+    assert_eq!(out, "mod foo {\n  fn bar() {\n    baz()\n  }\n}\n");
+}
+
+#[test]
+fn hard_tabs() {
+    let out = Config::new()
+        .hard_tabs(true)
+        .fmt(quote!(mod foo { fn bar() { baz() } }))
+        .unwrap();
+
+    assert_eq!(out, "mod foo {\n\tfn bar() {\n\t\tbaz()\n\t}\n}\n");
+}
+
+#[test]
+fn crlf_newlines() {
+    let file = syn::parse_file("fn foo() {\n    bar(\"a\nb\")\n}").unwrap();
+
+    let out = Config::new()
+        .newline_style(NewlineStyle::Crlf)
+        .fmt_file(&file);
+
+    // The newline inside of the string literal is part of its value
+    assert_eq!(out, "fn foo() {\r\n    bar(\"a\nb\")\r\n}\r\n");
+}
+
+#[test]
+fn max_width() {
+    let tts = quote!(fn foo() { call(first_argument, second_argument) });
+
+    let out = Config::new().max_width(40).fmt(tts.clone()).unwrap();
+    assert_eq!(out, "fn foo() {\n    call(\n        first_argument,\n        second_argument,\n    )\n}\n");
+
+    let out = Config::new().max_width(60).fmt(tts).unwrap();
+    assert_eq!(out, "fn foo() {\n    call(first_argument, second_argument)\n}\n");
+}
+
+#[test]
+fn trailing_comma_always() {
+    let out = Config::new()
+        .trailing_comma(TrailingComma::Always)
+        .fmt(quote! {
+            use std::{fmt, io};
+            struct Foo(u8, u16);
+            fn foo<T, U>(t: T, u: U) where T: Clone {
+                let S { a, b } = S { a: 1, b };
+                match t {
+                    A => bar(t, u),
+                    _ => baz(),
+                }
+            }
+        })
+        .unwrap();
+
+    let expect = r#"
+use std::{fmt, io,};
+
+struct Foo(u8, u16,);
+
+fn foo<T, U,>(t: T, u: U,)
+where
+    T: Clone,
+{
+    let S { a, b, } = S { a: 1, b, };
+    match t {
+        A => bar(t, u,),
+        _ => baz(),
+    }
+}
+"#;
+
+    assert_eq!(out, &expect[1..]);
+}
+
+#[test]
+fn trailing_comma_never() {
+    let out = Config::new()
+        .trailing_comma(TrailingComma::Never)
+        .max_width(40)
+        .fmt(quote! {
+            enum Foo { A, B { x: u8, y: u8, }, }
+            struct Bar { a: u8, b: u16, }
+            fn foo() {
+                match t {
+                    A => bar(first_argument, second_argument),
+                    _ => baz(),
+                }
+            }
+        })
+        .unwrap();
+
+    let expect = r#"
+enum Foo {
+    A,
+    B {
+        x: u8,
+        y: u8
+    }
+}
+
+struct Bar {
+    a: u8,
+    b: u16
+}
+
+fn foo() {
+    match t {
+        A => bar(
+            first_argument,
+            second_argument
+        ),
+        _ => baz()
+    }
+}
+"#;
+
+    assert_eq!(out, &expect[1..]);
+}
+
+#[test]
+fn blank_lines() {
+    let tts = quote! {
+        extern crate foo;
+        use std::fmt;
+        use std::io;
+        struct A;
+        struct B;
+        impl A {
+            fn a() {}
+            fn b() {}
+        }
+    };
+
+    let out = Config::new()
+        .blank_lines_between_items(2)
+        .blank_lines_between_impl_items(0)
+        .blank_lines_after_uses(3)
+        .fmt(tts)
+        .unwrap();
+
+    let expect = r#"
+extern crate foo;
+use std::fmt;
+use std::io;
+
+
+
+struct A;
+
+
+struct B;
+
+
+impl A {
+    fn a() {}
+    fn b() {}
+}
+"#;
+
+    assert_eq!(out, &expect[1..]);
+}
+
+#[test]
+fn expanded_empty_bodies() {
+    let out = Config::new()
+        .compact_empty_bodies(false)
+        .fmt(quote! {
+            impl Foo {}
+            fn foo() { match x {} }
+        })
+        .unwrap();
+
+    let expect = r#"
+impl Foo {
+}
+
+fn foo() {
+    match x {
+    }
+}
+"#;
+
+    assert_eq!(out, &expect[1..]);
+}
@@ -0,0 +1,803 @@
+#![recursion_limit="1024"]
+
+extern crate minifmt;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+
+#[macro_use]
+mod support;
+
+use proc_macro2::{Delimiter, Group};
+
+#[test]
+fn ranges() {
+    check! {
+        r#"
+impl Foo {
+    fn ranges() {
+        let a = ..;
+        let b = 1..;
+        let c = ..n;
+        let d = 1..n;
+        let e = ..=n;
+        let f = 1..=n;
+    }
+}
+"#,
+        impl Foo {
+            fn ranges() {
+                let a = ..;
+                let b = 1..;
+                let c = ..n;
+                let d = 1..n;
+                let e = ..=n;
+                let f = 1..=n;
+            }
+        }
+    }
+}
+
+#[test]
+fn indexing() {
+    check! {
+        r#"
+impl Foo {
+    fn indexing(&self) {
+        let a = buf[0];
+        let b = &buf[1..n];
+        let c = self.map[key][idx];
+    }
+}
+"#,
+        impl Foo {
+            fn indexing(&self) {
+                let a = buf[0];
+                let b = &buf[1..n];
+                let c = self.map[key][idx];
+            }
+        }
+    }
+}
+
+#[test]
+fn tuple_fields() {
+    check! {
+        r#"
+impl Foo {
+    fn tuple_fields(&self) -> Foo {
+        let a = self.0;
+        let b = self.1.inner.0;
+        Foo { 0: a, 1: b }
+    }
+}
+"#,
+        impl Foo {
+            fn tuple_fields(&self) -> Foo {
+                let a = self.0;
+                let b = self.1.inner.0;
+                Foo {
+                    0: a,
+                    1: b,
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn tuples() {
+    check! {
+        r#"
+impl Foo {
+    fn tuples() {
+        let a = ();
+        let b = (a,);
+        let c = (a, b);
+        let d = (a, (b, c), "three");
+    }
+}
+"#,
+        impl Foo {
+            fn tuples() {
+                let a = ();
+                let b = (a,);
+                let c = (a, b);
+                let d = (a, (b, c), "three",);
+            }
+        }
+    }
+}
+
+#[test]
+fn arrays() {
+    check! {
+        r#"
+impl Foo {
+    fn arrays() {
+        let a = [];
+        let b = [1, 2, 3];
+        let c = [0u8; 256];
+        let d = [
+            "one two three four five",
+            "six seven eight nine ten",
+            "eleven twelve thirteen fourteen",
+        ];
+    }
+}
+"#,
+        impl Foo {
+            fn arrays() {
+                let a = [];
+                let b = [1, 2, 3];
+                let c = [0u8; 256];
+                let d = ["one two three four five", "six seven eight nine ten", "eleven twelve thirteen fourteen"];
+            }
+        }
+    }
+}
+
+#[test]
+fn tables() {
+    check! {
+        r#"
+static CRC: [u32; 20] = [
+    0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535, 0x9e6495a3,
+    0x0edb8832, 0x79dcb8a4, 0xe0d5e91e, 0x97d2d988, 0x09b64c2b, 0x7eb17cbd, 0xe7b82d07, 0x90bf1d91,
+    0x1db71064, 0x6ab020f2, 0xf3b97148, 0x84be41de,
+];
+
+fn tables() {
+    let short = [1, -2, 3];
+    let deltas = [
+           0,    1,   -1,   12,  -12,  123, -123, 1234,    0,    1,   -1,   12,  -12,  123, -123,
+        1234,    0,    1,   -1,   12,  -12,  123, -123, 1234,
+    ];
+}
+"#,
+        static CRC: [u32; 20] = [
+            0x00000000, 0x77073096, 0xee0e612c, 0x990951ba, 0x076dc419, 0x706af48f, 0xe963a535,
+            0x9e6495a3, 0x0edb8832, 0x79dcb8a4, 0xe0d5e91e, 0x97d2d988, 0x09b64c2b, 0x7eb17cbd,
+            0xe7b82d07, 0x90bf1d91, 0x1db71064, 0x6ab020f2, 0xf3b97148, 0x84be41de
+        ];
+        fn tables() {
+            let short = [1, -2, 3];
+            let deltas = [
+                0, 1, -1, 12, -12, 123, -123, 1234, 0, 1, -1, 12, -12, 123, -123, 1234,
+                0, 1, -1, 12, -12, 123, -123, 1234,
+            ];
+        }
+    }
+}
+
+#[test]
+fn assignment() {
+    check! {
+        r#"
+impl Foo {
+    fn assignment(&mut self) {
+        self.state = State::Done;
+        self.buf[i] = x;
+        self.0.len -= 1;
+        count += 1;
+        flags |= 1 << n;
+        mask <<= 2;
+    }
+}
+"#,
+        impl Foo {
+            fn assignment(&mut self) {
+                self.state = State::Done;
+                self.buf[i] = x;
+                self.0.len -= 1;
+                count += 1;
+                flags |= 1 << n;
+                mask <<= 2;
+            }
+        }
+    }
+}
+
+#[test]
+fn return_and_yield() {
+    check! {
+        r#"
+impl Foo {
+    fn return_and_yield() {
+        if done {
+            return;
+        }
+        if found {
+            return Some(value);
+        }
+        yield;
+        yield value;
+    }
+}
+"#,
+        impl Foo {
+            fn return_and_yield() {
+                if done {
+                    return;
+                }
+                if found {
+                    return Some(value);
+                }
+                yield;
+                yield value;
+            }
+        }
+    }
+}
+
+#[test]
+fn unsafe_and_async_blocks() {
+    check! {
+        r#"
+impl Foo {
+    fn blocks() {
+        let a = unsafe {
+            ptr.read()
+        };
+        let b = async {
+            foo()
+        };
+        let c = async move {
+            bar(a, b)
+        };
+    }
+}
+"#,
+        impl Foo {
+            fn blocks() {
+                let a = unsafe {
+                    ptr.read()
+                };
+                let b = async {
+                    foo()
+                };
+                let c = async move {
+                    bar(a, b)
+                };
+            }
+        }
+    }
+}
+
+#[test]
+fn parens() {
+    check! {
+        r#"
+impl Foo {
+    fn parens() {
+        let a = (a + b) * c;
+        let b = -(x - 1);
+        let c = ((a));
+        let d = (a.b)();
+    }
+}
+"#,
+        impl Foo {
+            fn parens() {
+                let a = (a + b) * c;
+                let b = -(x - 1);
+                let c = ((a));
+                let d = (a.b)();
+            }
+        }
+    }
+}
+
+#[test]
+fn invisible_groups() {
+    let sum = Group::new(Delimiter::None, quote!(a + b));
+    let product = Group::new(Delimiter::None, quote!(a * b));
+    let cmp = Group::new(Delimiter::None, quote!(a < b));
+    let field = Group::new(Delimiter::None, quote!(s.f));
+    let one = Group::new(Delimiter::None, quote!(1));
+
+    check! {
+        r#"
+impl Foo {
+    fn invisible_groups() {
+        let a = a + b;
+        let b = (a + b) * 2;
+        let c = a * b + 2;
+        let d = 2 - (a + b);
+        let e = -(a + b);
+        let f = &(a + b);
+        let g = (a < b) == c;
+        x = a + b;
+        let h = (s.f)();
+        let i = (1).0;
+    }
+}
+"#,
+        impl Foo {
+            fn invisible_groups() {
+                let a = #sum;
+                let b = #sum * 2;
+                let c = #product + 2;
+                let d = 2 - #sum;
+                let e = -#sum;
+                let f = &#sum;
+                let g = #cmp == c;
+                x = #sum;
+                let h = #field();
+                let i = #one.0;
+            }
+        }
+    }
+}
+
+#[test]
+fn invisible_group_casts() {
+    let cast = Group::new(Delimiter::None, quote!(a as u8));
+    let sum = Group::new(Delimiter::None, quote!(x + a as u8));
+
+    check! {
+        r#"
+fn foo() {
+    let a = (a as u8) < 3;
+    let b = (a as u8) << 3;
+    let c = a as u8 > 3;
+    let d = (x + a as u8) < 3;
+}
+"#,
+        fn foo() {
+            let a = #cast < 3;
+            let b = #cast << 3;
+            let c = #cast > 3;
+            let d = #sum < 3;
+        }
+    }
+}
+
+#[test]
+fn invisible_group_struct_literals() {
+    let cmp = Group::new(Delimiter::None, quote!(S { a: 1 } == y));
+    let lit = Group::new(Delimiter::None, quote!(S { a: 1 }));
+
+    check! {
+        r#"
+fn foo() {
+    if (S { a: 1 } == y) {}
+    while (S { a: 1 } == y) {}
+    match (S { a: 1 }) {
+        _ => 0,
+    }
+    for x in (S { a: 1 }) {}
+    if let S { a } = (S { a: 1 }) {}
+    let b = S { a: 1 } == y;
+}
+"#,
+        fn foo() {
+            if #cmp {}
+            while #cmp {}
+            match #lit { _ => 0 }
+            for x in #lit {}
+            if let S { a } = #lit {}
+            let b = #cmp;
+        }
+    }
+}
+
+#[test]
+fn prefix_jump_operands() {
+    let closure = Group::new(Delimiter::None, quote!(&|x| x));
+
+    check! {
+        r#"
+fn foo() {
+    let a = &mut |x| x + 1;
+    let b = !break;
+    let c = &return;
+    let d = &&|x| x;
+    let e = (&|x| x) + 1;
+    let f = (&|x| x).call();
+}
+"#,
+        fn foo() {
+            let a = &mut |x| x + 1;
+            let b = !break;
+            let c = &return;
+            let d = &&|x| x;
+            let e = #closure + 1;
+            let f = #closure.call();
+        }
+    }
+}
+
+#[test]
+fn invisible_type_groups() {
+    let ty = Group::new(Delimiter::None, quote!(Vec<u8>));
+    let dyn_ty = Group::new(Delimiter::None, quote!(dyn std::fmt::Debug + Send));
+    let impl_ty = Group::new(Delimiter::None, quote!(impl Fn() -> u8 + Send));
+
+    check! {
+        r#"
+impl Foo {
+    fn invisible_type_groups(buf: &Vec<u8>, f: &(impl Fn() -> u8 + Send)) {
+        let a: Vec<u8> = Vec::new();
+        let b: &(dyn std::fmt::Debug + Send) = &a;
+        let c: &mut (dyn std::fmt::Debug + Send) = &mut a;
+        let d: *const (dyn std::fmt::Debug + Send) = p;
+        let e: fn() -> (dyn std::fmt::Debug + Send) = g;
+        let f: Box<dyn std::fmt::Debug + Send> = h;
+    }
+}
+"#,
+        impl Foo {
+            fn invisible_type_groups(buf: &#ty, f: &#impl_ty) {
+                let a: #ty = Vec::new();
+                let b: &#dyn_ty = &a;
+                let c: &mut #dyn_ty = &mut a;
+                let d: *const #dyn_ty = p;
+                let e: fn() -> #dyn_ty = g;
+                let f: Box<#dyn_ty> = h;
+            }
+        }
+    }
+}
+
+#[test]
+fn turbofish() {
+    check! {
+        r#"
+impl Foo {
+    fn turbofish() {
+        let a = iter.collect::<Vec<_>>();
+        let b = s.parse::<u32>();
+        let c = map.get::<str, _>(key);
+        let d = mem::size_of::<T>();
+        let e = Vec::<u8>::with_capacity(n);
+    }
+}
+"#,
+        impl Foo {
+            fn turbofish() {
+                let a = iter.collect::<Vec<_>>();
+                let b = s.parse::<u32>();
+                let c = map.get::<str, _>(key);
+                let d = mem::size_of::<T>();
+                let e = Vec::<u8>::with_capacity(n);
+            }
+        }
+    }
+}
+
+#[test]
+fn struct_literals() {
+    check! {
+        r#"
+impl Foo {
+    fn struct_literals() {
+        let a = Foo {};
+        let b = Foo { a, b };
+        let c = Point { x: 1, y: 2 };
+        let d = Foo {
+            a,
+            b: other.b,
+            c: "three",
+        };
+        let e = Foo { ..base };
+        let f = Config {
+            name,
+            ..Default::default()
+        };
+    }
+}
+"#,
+        impl Foo {
+            fn struct_literals() {
+                let a = Foo {};
+                let b = Foo { a, b };
+                let c = Point { x: 1, y: 2 };
+                let d = Foo { a, b: other.b, c: "three" };
+                let e = Foo { ..base };
+                let f = Config { name, .. Default::default() };
+            }
+        }
+    }
+}
+
+#[test]
+fn closures() {
+    check! {
+        r#"
+fn closures() {
+    let a = || 1;
+    let b = |x| x + 1;
+    let c = move |a: u32, b| -> u32 {
+        a + b
+    };
+    let d = async move || foo();
+    iter.map(|(k, v)| k);
+}
+"#,
+        fn closures() {
+            let a = || 1;
+            let b = |x| x + 1;
+            let c = move |a: u32, b| -> u32 {
+                a + b
+            };
+            let d = async move || foo();
+            iter.map(|(k, v)| k);
+        }
+    }
+}
+
+#[test]
+fn call_arguments() {
+    check! {
+        r#"
+fn call_arguments() {
+    let short = foo(a, b, c);
+    let long = some_function_name(
+        first_argument_value,
+        second_argument_value,
+        third_argument_value,
+        fourth,
+    );
+    let nested = outer(
+        inner(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb),
+        ccccccccccccccccccccccccccc,
+    );
+    iter.for_each(|x| {
+        bar(x);
+    });
+}
+"#,
+        fn call_arguments() {
+            let short = foo(a, b, c);
+            let long = some_function_name(first_argument_value, second_argument_value, third_argument_value, fourth);
+            let nested = outer(inner(aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa, bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb), ccccccccccccccccccccccccccc);
+            iter.for_each(|x| { bar(x); });
+        }
+    }
+}
+
+#[test]
+fn binary_chains() {
+    check! {
+        r#"
+fn binary_chains() {
+    let short = a + b * c;
+    let ok = self.first_name == other.first_name
+        && self.last_name == other.last_name
+        && self.age == other.age;
+    let total = base_amount_in_cents * quantity_ordered
+        + shipping_cost_in_cents
+        + handling_fee_in_cents
+        - discount;
+    total_accumulated_value_for_this_run += some_long_function_name(argument_one, two) * 2;
+    let product = first_factor_with_a_long_name * second_factor_with_a_long_name
+        + third_operand_name;
+    let either = first_condition_with_a_long_name && second_condition_with_a_long_name
+        || third_condition;
+    let nested = first_factor_with_a_much_longer_name_than_before
+        * second_factor_with_a_much_longer_name_than_before
+        + c;
+    first_statement_operand_with_a_long_name * second_statement_operand_with_a_longer_name
+        + third_operand;
+}
+"#,
+        fn binary_chains() {
+            let short = a + b * c;
+            let ok = self.first_name == other.first_name && self.last_name == other.last_name && self.age == other.age;
+            let total = base_amount_in_cents * quantity_ordered + shipping_cost_in_cents + handling_fee_in_cents - discount;
+            total_accumulated_value_for_this_run += some_long_function_name(argument_one, two) * 2;
+            let product = first_factor_with_a_long_name * second_factor_with_a_long_name + third_operand_name;
+            let either = first_condition_with_a_long_name && second_condition_with_a_long_name || third_condition;
+            let nested = first_factor_with_a_much_longer_name_than_before * second_factor_with_a_much_longer_name_than_before + c;
+            first_statement_operand_with_a_long_name * second_statement_operand_with_a_longer_name + third_operand;
+        }
+    }
+}
+
+#[test]
+fn method_chains() {
+    check! {
+        r#"
+async fn method_chains() {
+    let v: Vec<_> = self.items.iter().map(|x| x + 1).collect();
+    let client = Client::builder()
+        .timeout(Duration::from_secs(30))
+        .retry(RetryPolicy::exponential(3))
+        .build()?;
+    let names = self.items
+        .iter()
+        .filter(|item| item.is_visible())
+        .map(|item| item.name.clone())
+        .collect::<Vec<_>>();
+    let data = fetch(url).await?
+        .json::<Response>()
+        .await?
+        .data
+        .into_iter()
+        .filter_map(parse)
+        .collect::<Vec<_>>();
+}
+"#,
+        async fn method_chains() {
+            let v: Vec<_> = self.items.iter().map(|x| x + 1).collect();
+            let client = Client::builder().timeout(Duration::from_secs(30)).retry(RetryPolicy::exponential(3)).build()?;
+            let names = self.items.iter().filter(|item| item.is_visible()).map(|item| item.name.clone()).collect::<Vec<_>>();
+            let data = fetch(url).await?.json::<Response>().await?.data.into_iter().filter_map(parse).collect::<Vec<_>>();
+        }
+    }
+}
+
+#[test]
+fn await_expressions() {
+    check! {
+        r#"
+async fn fetch(url: &str) -> Result<Body, Error> {
+    let resp = client.get(url).send().await?;
+    let body = (a + b).await;
+    resp.body().await
+}
+"#,
+        async fn fetch(url: &str) -> Result<Body, Error> {
+            let resp = client.get(url).send().await?;
+            let body = (a + b).await;
+            resp.body().await
+        }
+    }
+}
+
+#[test]
+fn let_else() {
+    check! {
+        r#"
+fn let_else(opt: Option<u8>) -> u8 {
+    let Some(x) = opt else {
+        return 0;
+    };
+    let Ok(y): Result<u8, E> = parse(s) else {
+        panic!("bad")
+    };
+    x + y
+}
+"#,
+        fn let_else(opt: Option<u8>) -> u8 {
+            let Some(x) = opt else {
+                return 0;
+            };
+            let Ok(y): Result<u8, E> = parse(s) else {
+                panic!("bad")
+            };
+            x + y
+        }
+    }
+}
+
+#[test]
+fn let_chains() {
+    check! {
+        r#"
+fn let_chains() {
+    if let Some(a) = x && a > 0 && let Ok(b) = (y || z) {
+        foo(a, b);
+    }
+    while let Some(x) = iter.next() {
+        bar(x);
+    }
+}
+"#,
+        fn let_chains() {
+            if let Some(a) = x && a > 0 && let Ok(b) = (y || z) {
+                foo(a, b);
+            }
+            while let Some(x) = iter.next() {
+                bar(x);
+            }
+        }
+    }
+}
+
+#[test]
+fn labels() {
+    check! {
+        r#"
+fn labels() {
+    let a = 'outer: {
+        if done {
+            break 'outer 1;
+        }
+        2
+    };
+    'rows: for row in rows {
+        'cols: loop {
+            continue 'rows;
+        }
+    }
+}
+"#,
+        fn labels() {
+            let a = 'outer: {
+                if done {
+                    break 'outer 1;
+                }
+                2
+            };
+            'rows: for row in rows {
+                'cols: loop {
+                    continue 'rows;
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn const_and_try_blocks() {
+    check! {
+        r#"
+fn blocks() {
+    let a = const {
+        N * 2
+    };
+    let b = try {
+        foo()?.bar()?
+    };
+}
+"#,
+        fn blocks() {
+            let a = const { N * 2 };
+            let b = try { foo()?.bar()? };
+        }
+    }
+}
+
+#[test]
+fn raw_references() {
+    check! {
+        r#"
+fn raw_references() {
+    let a = &raw const packed.field;
+    let b = &raw mut *ptr;
+    let c = a as *const u8;
+}
+"#,
+        fn raw_references() {
+            let a = &raw const packed.field;
+            let b = &raw mut *ptr;
+            let c = a as *const u8;
+        }
+    }
+}
+
+#[test]
+fn box_expressions_are_rejected() {
+    // syn 2 no longer parses the unstable `box` syntax
+    assert!(minifmt::fmt(quote!(fn foo() { let a = box 1; })).is_err());
+}
+
+#[test]
+fn block_arms() {
+    check! {
+        r#"
+fn foo() {
+    match x {
+        A => 1,
+        B => {
+            2
+        }
+    }
+    match y {
+        _ => {}
+    }
+}
+"#,
+        fn foo() {
+            match x {
+                A => 1,
+                B => { 2 }
+            }
+            match y {
+                _ => {},
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+extern crate minifmt;
+extern crate syn;
+
+#[test]
+fn shebang() {
+    let src = "#!/usr/bin/env run-cargo-script\n#![allow(dead_code)] use std::io; mod foo;";
+    let file = syn::parse_file(src).unwrap();
+
+    let expect = r#"
+#!/usr/bin/env run-cargo-script
+#![allow(dead_code)]
+use std::io;
+
+mod foo;
+"#;
+
+    assert_eq!(minifmt::fmt_file(&file), &expect[1..]);
+}
+
+#[test]
+fn no_shebang() {
+    let file = syn::parse_file("use std::io;").unwrap();
+
+    assert_eq!(minifmt::fmt_file(&file), "use std::io;\n");
+}
@@ -11,11 +11,9 @@ mod support;
 fn empty_impl_block() {
     check! {
         r#"
-impl MyStruct {
-}
+impl MyStruct {}
 "#,
-        impl MyStruct {
-        }
+        impl MyStruct {}
     }
 }
 
@@ -62,8 +60,7 @@ impl Foo {
     where
         S: Service<Request>,
         S::Future: Send + 'static,
-    {
-    }
+    {}
 }
 "#,
         impl Foo {
@@ -71,8 +68,7 @@ impl Foo {
             where
                 S: Service<Request>,
                 S::Future: Send + 'static,
-            {
-            }
+            {}
         }
     }
 }
@@ -85,15 +81,13 @@ impl<S, Request> Foo
 where
     S: Service<Request>,
     S::Future: Send + 'static,
-{
-}
+{}
 "#,
         impl<S, Request> Foo
         where
             S: Service<Request>,
             S::Future: Send + 'static,
-        {
-        }
+        {}
     }
 }
 
@@ -0,0 +1,334 @@
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn functions() {
+    check! {
+        r#"
+fn foo() {
+    bar()
+}
+
+pub const unsafe extern "C" fn baz<T>(t: T) -> T {
+    t
+}
+"#,
+        fn foo() {
+            bar()
+        }
+        pub const unsafe extern "C" fn baz<T>(t: T) -> T {
+            t
+        }
+    }
+}
+
+#[test]
+fn traits() {
+    check! {
+        r#"
+pub unsafe trait Foo<T>: Clone + Send {
+    const N: usize;
+
+    const M: usize = 1;
+
+    type Item: Debug;
+
+    fn required(&self) -> T;
+
+    fn provided(&self) -> usize {
+        Self::N
+    }
+}
+"#,
+        pub unsafe trait Foo<T>: Clone + Send {
+            const N: usize;
+            const M: usize = 1;
+            type Item: Debug;
+            fn required(&self) -> T;
+            fn provided(&self) -> usize {
+                Self::N
+            }
+        }
+    }
+}
+
+#[test]
+fn extern_blocks() {
+    check! {
+        r#"
+extern "C" {
+    pub fn abs(input: i32) -> i32;
+    static mut errno: i32;
+    type Opaque;
+}
+"#,
+        extern "C" {
+            pub fn abs(input: i32) -> i32;
+            static mut errno: i32;
+            type Opaque;
+        }
+    }
+}
+
+#[test]
+fn items_end_with_newline() {
+    check! {
+        r#"
+struct A;
+
+mod b;
+
+struct C;
+"#,
+        struct A;
+        mod b;
+        struct C;
+    }
+}
+
+#[test]
+fn const_generics() {
+    check! {
+        r#"
+struct Buf<const N: usize> {
+    data: [u8; N],
+}
+
+fn with_default<T, const N: usize = 4>() -> Buf<N> {
+    let a = Buf::<N>::new();
+    Foo::<{ N + 1 }>::new()
+}
+"#,
+        struct Buf<const N: usize> {
+            data: [u8; N],
+        }
+        fn with_default<T, const N: usize = 4>() -> Buf<N> {
+            let a = Buf::<N>::new();
+            Foo::<{ N + 1 }>::new()
+        }
+    }
+}
+
+#[test]
+fn generic_associated_types() {
+    check! {
+        r#"
+trait Lend {
+    type Item<'a>
+    where
+        Self: 'a;
+
+    fn lend<'a>(&'a mut self) -> Self::Item<'a>;
+}
+
+impl<T> Lend for Vec<T> {
+    type Item<'a> = &'a T
+    where
+        Self: 'a;
+}
+"#,
+        trait Lend {
+            type Item<'a> where Self: 'a;
+            fn lend<'a>(&'a mut self) -> Self::Item<'a>;
+        }
+        impl<T> Lend for Vec<T> {
+            type Item<'a> = &'a T where Self: 'a;
+        }
+    }
+}
+
+#[test]
+fn impl_trait() {
+    check! {
+        r#"
+fn adapt(iter: impl Iterator<Item = u8>) -> impl Fn(u8) -> u8 + Send + use<> {
+    move |x| x
+}
+
+fn bounded() -> impl Iterator<Item: Clone> {
+    iter()
+}
+"#,
+        fn adapt(iter: impl Iterator<Item = u8>) -> impl Fn(u8) -> u8 + Send + use<> {
+            move |x| x
+        }
+        fn bounded() -> impl Iterator<Item: Clone> {
+            iter()
+        }
+    }
+}
+
+#[test]
+fn trait_objects() {
+    check! {
+        r#"
+fn trait_objects(
+    a: Box<dyn Error + Send + Sync>,
+    b: &dyn* Future<Output = ()>,
+    c: &(dyn Fn() + 'static),
+) {
+    let f: fn(u8) -> ! = abort;
+}
+"#,
+        fn trait_objects(a: Box<dyn Error + Send + Sync>, b: &dyn* Future<Output = ()>, c: &(dyn Fn() + 'static)) {
+            let f: fn(u8) -> ! = abort;
+        }
+    }
+}
+
+#[test]
+fn long_signatures() {
+    check! {
+        r#"
+trait Service {
+    fn handle_request(
+        &self,
+        request_identifier: RequestId,
+        payload: &[u8],
+        headers: &HeaderMap,
+        timeout: Duration,
+    ) -> Result<Response, Error>;
+}
+
+fn process<
+    TInput: Clone + Send + 'static,
+    TOutput: Default + Debug,
+    TError: std::error::Error + Send + Sync,
+>(input: TInput) -> TOutput
+where
+    TInput: Copy,
+{
+    convert(input)
+}
+
+fn long_return() -> Result<
+    HashMap<StringKeyTypeName, Vec<SomeVeryLongValueTypeName>>,
+    SomeVeryLongErrorTypeName,
+> {
+    load()
+}
+
+extern "C" {
+    fn printf(
+        format: *const c_char,
+        aaaaaaaaaaaaaaaaaaaaa: u32,
+        bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb: u32,
+        ...
+    ) -> c_int;
+}
+"#,
+        trait Service {
+            fn handle_request(&self, request_identifier: RequestId, payload: &[u8], headers: &HeaderMap, timeout: Duration) -> Result<Response, Error>;
+        }
+        fn process<TInput: Clone + Send + 'static, TOutput: Default + Debug, TError: std::error::Error + Send + Sync>(input: TInput) -> TOutput
+        where
+            TInput: Copy,
+        {
+            convert(input)
+        }
+        fn long_return() -> Result<HashMap<StringKeyTypeName, Vec<SomeVeryLongValueTypeName>>, SomeVeryLongErrorTypeName> {
+            load()
+        }
+        extern "C" {
+            fn printf(format: *const c_char, aaaaaaaaaaaaaaaaaaaaa: u32, bbbbbbbbbbbbbbbbbbbbbbbbbbbbbb: u32, ...) -> c_int;
+        }
+    }
+}
+
+#[test]
+fn long_bounds() {
+    check! {
+        r#"
+fn bounded<T>(value: T)
+where
+    T: Clone
+        + Debug
+        + Send
+        + Sync
+        + 'static
+        + Serialize
+        + DeserializeOwned
+        + PartialEq
+        + Eq
+        + Hash
+        + Default,
+{
+    store(value)
+}
+"#,
+        fn bounded<T>(value: T)
+        where
+            T: Clone + Debug + Send + Sync + 'static + Serialize + DeserializeOwned + PartialEq + Eq + Hash + Default,
+        {
+            store(value)
+        }
+    }
+}
+
+#[test]
+fn empty_bodies() {
+    check! {
+        r#"
+trait Marker {}
+
+enum Never {}
+
+extern "C" {}
+
+fn noop() {}
+
+fn wait(x: Never) {
+    match x {}
+    loop {}
+}
+
+fn check(a: bool) {
+    if a {
+    } else {
+        b()
+    }
+    if a {
+    } else {
+    }
+    if a {
+    } else if b {
+    }
+    if a {}
+}
+"#,
+        trait Marker {}
+        enum Never {}
+        extern "C" {}
+        fn noop() {}
+        fn wait(x: Never) {
+            match x {}
+            loop {}
+        }
+        fn check(a: bool) {
+            if a {} else { b() }
+            if a {} else {}
+            if a {} else if b {}
+            if a {}
+        }
+    }
+}
+
+#[test]
+fn unsafe_extern_blocks() {
+    check! {
+        r#"
+unsafe extern "C" {
+    pub safe fn abs(x: i32) -> i32;
+    pub unsafe static ERRNO: i32;
+}
+"#,
+        unsafe extern "C" {
+            pub safe fn abs(x: i32) -> i32;
+            pub unsafe static ERRNO: i32;
+        }
+    }
+}
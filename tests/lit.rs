@@ -0,0 +1,134 @@
+#![recursion_limit="1024"]
+
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn integers() {
+    check! {
+        r#"
+impl Foo {
+    fn integers() {
+        let a = 1_000_000;
+        let b = 0xFF_u8;
+        let c = 0o755;
+        let d = 0b1010_1010;
+        let e = 340282366920938463463374607431768211455u128;
+        let f = 42usize;
+    }
+}
+"#,
+        impl Foo {
+            fn integers() {
+                let a = 1_000_000;
+                let b = 0xFF_u8;
+                let c = 0o755;
+                let d = 0b1010_1010;
+                let e = 340282366920938463463374607431768211455u128;
+                let f = 42usize;
+            }
+        }
+    }
+}
+
+#[test]
+fn floats_and_bools() {
+    check! {
+        r#"
+impl Foo {
+    fn floats_and_bools() {
+        let a = 1.5;
+        let b = 2.0f32;
+        let c = 1e-7;
+        let d = true;
+        let e = false;
+    }
+}
+"#,
+        impl Foo {
+            fn floats_and_bools() {
+                let a = 1.5;
+                let b = 2.0f32;
+                let c = 1e-7;
+                let d = true;
+                let e = false;
+            }
+        }
+    }
+}
+
+#[test]
+fn chars_and_bytes() {
+    check! {
+        r#"
+impl Foo {
+    fn chars_and_bytes() {
+        let a = 'a';
+        let b = '\n';
+        let c = '\u{1F600}';
+        let d = b'a';
+        let e = b'\x7f';
+        let f = b"bytes\r\n";
+        let g = br"raw bytes";
+    }
+}
+"#,
+        impl Foo {
+            fn chars_and_bytes() {
+                let a = 'a';
+                let b = '\n';
+                let c = '\u{1F600}';
+                let d = b'a';
+                let e = b'\x7f';
+                let f = b"bytes\r\n";
+                let g = br"raw bytes";
+            }
+        }
+    }
+}
+
+#[test]
+fn strings() {
+    check! {
+        r##"
+impl Foo {
+    fn strings() {
+        let a = "escaped \"quote\"\t";
+        let b = r"raw \n";
+        let c = r#"raw "quoted""#;
+    }
+}
+"##,
+        impl Foo {
+            fn strings() {
+                let a = "escaped \"quote\"\t";
+                let b = r"raw \n";
+                let c = r#"raw "quoted""#;
+            }
+        }
+    }
+}
+
+#[test]
+fn multi_line_strings() {
+    check! {
+        r##"
+impl Foo {
+    fn multi_line_strings() {
+        let a = "first
+second";
+    }
+}
+"##,
+        impl Foo {
+            fn multi_line_strings() {
+                let a = "first
+second";
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn delimiters() {
+    check! {
+        r#"
+fn foo() {
+    let a = vec![1, 2];
+    let b = format!("{}", a);
+    let c = m! {};
+    println!("{:?}", c);
+}
+"#,
+        fn foo() {
+            let a = vec![1, 2];
+            let b = format!("{}", a);
+            let c = m!{};
+            println!("{:?}", c);
+        }
+    }
+}
+
+#[test]
+fn item_macros() {
+    check! {
+        r#"
+lazy_static! {
+    static ref A: u32 = 1;
+    static ref B: Vec<u8> = Vec::new();
+}
+
+thread_local!(static FOO: u32 = 1);
+
+macro_rules! foo {
+    () => {};
+}
+"#,
+        lazy_static! {
+            static ref A: u32 = 1;
+            static ref B: Vec<u8> = Vec::new();
+        }
+        thread_local!(static FOO: u32 = 1);
+        macro_rules! foo {
+            () => {};
+        }
+    }
+}
+
+#[test]
+fn statement_macros() {
+    check! {
+        r#"
+fn foo() {
+    thread_local!(static FOO: u32 = 1);
+    m! {
+        a;
+    }
+    m! {
+        b;
+    };
+    assert!(true);
+}
+"#,
+        fn foo() {
+            thread_local!(static FOO: u32 = 1);
+            m! { a; }
+            m! { b; };
+            assert!(true);
+        }
+    }
+}
+
+#[test]
+fn impl_item_macros() {
+    check! {
+        r#"
+impl Foo {
+    delegate!(len);
+
+    delegate! {
+        is_empty;
+    }
+}
+"#,
+        impl Foo {
+            delegate!(len);
+            delegate! { is_empty; }
+        }
+    }
+}
+
+#[test]
+fn macro_operators() {
+    check! {
+        r#"
+fn foo() {
+    let a = vec![Vec::<u8>::new()];
+    println!("{}", s.parse::<u32>().unwrap());
+    assert!(MAX < LIMIT && x > 0);
+    let b = m!(Vec<Vec<u8>>, a <= b, a -> b);
+    m!(& &x);
+    m!(&&x);
+    m!(let.. = x);
+    m!(.. .);
+}
+"#,
+        fn foo() {
+            let a = vec![Vec::<u8>::new()];
+            println!("{}", s.parse::<u32>().unwrap());
+            assert!(MAX < LIMIT && x > 0);
+            let b = m!(Vec<Vec<u8>>, a <= b, a -> b);
+            m!(& &x);
+            m!(&&x);
+            m!(let .. = x);
+            m!(.. .);
+        }
+    }
+}
+
+#[test]
+fn macro_closures() {
+    check! {
+        r#"
+fn foo() {
+    m!(|y| y + 1);
+    m!(move |y| y, || 1);
+}
+"#,
+        fn foo() {
+            m!(|y| y + 1);
+            m!(move |y| y, || 1);
+        }
+    }
+}
+
+#[test]
+fn macro_metavariables() {
+    check! {
+        r#"
+macro_rules! foo {
+    ($x:expr, $t:ty) => { $x as $t };
+}
+"#,
+        macro_rules! foo {
+            ($x:expr, $t:ty) => { $x as $t };
+        }
+    }
+}
+
+#[test]
+fn macro_repetitions() {
+    check! {
+        r#"
+macro_rules! foo {
+    ($($x:expr),* $(,)?) => { vec![$($x),*] };
+    ($($x:ident)+) => { $(let $x = 1;)* };
+}
+"#,
+        macro_rules! foo {
+            ($($x:expr),* $(,)?) => { vec![$($x),*] };
+            ($($x:ident)+) => { $(let $x = 1;)* };
+        }
+    }
+
+    let src = "fn foo() {\n    quote! { impl X { #(#methods)* } }\n    m! { a; b * }\n}";
+    let file = syn::parse_file(src).unwrap();
+
+    assert_eq!(
+        minifmt::fmt_file(&file),
+        "fn foo() {\n    quote! {\n        impl X { #(#methods)* }\n    }\n    m! {\n        a;\n        b *\n    }\n}\n"
+    );
+}
+
+#[test]
+fn multi_line_literal_in_macro() {
+    let file = syn::parse_file("fn foo() {\n    println!(\"first\nsecond\");\n}").unwrap();
+
+    assert_eq!(minifmt::fmt_file(&file), "fn foo() {\n    println!(\"first\nsecond\");\n}\n");
+}
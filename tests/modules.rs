@@ -23,8 +23,7 @@ pub mod my_module;
 
     check! {
         r#"
-pub mod my_module {
-}
+pub mod my_module {}
 "#,
         pub mod my_module { }
     }
@@ -0,0 +1,174 @@
+#![recursion_limit="1024"]
+
+extern crate minifmt;
+#[macro_use]
+extern crate quote;
+
+#[macro_use]
+mod support;
+
+#[test]
+fn struct_patterns() {
+    check! {
+        r#"
+impl Foo {
+    fn struct_patterns() {
+        match foo {
+            Foo { a, ref b, .. } => (),
+            Foo { a: 1, b: ref mut c } => (),
+            Foo { .. } => (),
+            Foo {} => (),
+            Tuple { 0: a, 1: b } => (),
+        }
+    }
+}
+"#,
+        impl Foo {
+            fn struct_patterns() {
+                match foo {
+                    Foo { a, ref b, .. } => (),
+                    Foo { a: 1, b: ref mut c } => (),
+                    Foo { .. } => (),
+                    Foo {} => (),
+                    Tuple { 0: a, 1: b } => (),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn tuple_patterns() {
+    check! {
+        r#"
+impl Foo {
+    fn tuple_patterns() {
+        match foo {
+            (a,) => (),
+            (a, b) => (),
+            (first, ..) => (),
+            (.., last) => (),
+            (first, .., last) => (),
+            Some(x) => (),
+            Foo(a, ..) => (),
+        }
+    }
+}
+"#,
+        impl Foo {
+            fn tuple_patterns() {
+                match foo {
+                    (a,) => (),
+                    (a, b) => (),
+                    (first, ..) => (),
+                    (.., last) => (),
+                    (first, .., last) => (),
+                    Some(x) => (),
+                    Foo(a, ..) => (),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn slice_patterns() {
+    check! {
+        r#"
+impl Foo {
+    fn slice_patterns() {
+        match foo {
+            [] => (),
+            [a] => (),
+            [first, .., last] => (),
+            [first, ..] => (),
+            [.., last] => (),
+        }
+    }
+}
+"#,
+        impl Foo {
+            fn slice_patterns() {
+                match foo {
+                    [] => (),
+                    [a] => (),
+                    [first, .., last] => (),
+                    [first, ..] => (),
+                    [.., last] => (),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn other_patterns() {
+    check! {
+        r#"
+impl Foo {
+    fn other_patterns() {
+        match foo {
+            1..=9 => (),
+            'a'..='z' => (),
+            -1 => (),
+            "str" => (),
+            &x => (),
+            &mut (a, b) => (),
+            box x => (),
+            x @ Some(_) => (),
+            ref mut y @ 1..=5 => (),
+            None => (),
+            Ordering::Less => (),
+            <T as Trait>::CONST => (),
+            pat!() => (),
+            _ => (),
+        }
+    }
+}
+"#,
+        impl Foo {
+            fn other_patterns() {
+                match foo {
+                    1..=9 => (),
+                    'a'..='z' => (),
+                    -1 => (),
+                    "str" => (),
+                    &x => (),
+                    &mut (a, b) => (),
+                    box x => (),
+                    x @ Some(_) => (),
+                    ref mut y @ 1..=5 => (),
+                    None => (),
+                    Ordering::Less => (),
+                    <T as Trait>::CONST => (),
+                    pat!() => (),
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn nested_or_and_rest_patterns() {
+    check! {
+        r#"
+fn patterns() {
+    match x {
+        Some(1 | 2) | None => (),
+        [first, .., last] => (),
+        [head, rest @ ..] => (),
+        Point { x: 0 | 1, .. } => (),
+    }
+}
+"#,
+        fn patterns() {
+            match x {
+                Some(1 | 2) | None => (),
+                [first, .., last] => (),
+                [head, rest @ ..] => (),
+                Point { x: 0 | 1, .. } => (),
+            }
+        }
+    }
+}
@@ -23,8 +23,7 @@ pub struct MyStruct;
 
     check! {
         r#"
-pub struct MyStruct {
-}
+pub struct MyStruct {}
 "#,
         pub struct MyStruct { }
     }
@@ -69,12 +68,10 @@ fn derive() {
     check! {
         r#"
 #[derive(Foo, Bar, Baz)]
-struct MyStruct {
-}
+struct MyStruct {}
 "#,
         #[derive(Foo, Bar, Baz)]
-        struct MyStruct {
-        }
+        struct MyStruct {}
     }
 }
 